@@ -0,0 +1,204 @@
+use super::geometry::{Size, AABB};
+use super::traits::*;
+use super::QuadTree;
+
+/// Configures and creates a `QuadTree`
+///
+/// Usage:
+/// ```ignore
+/// let qt: QuadTree<Obj> = QuadTreeBuilder::new()
+///     .zone(AABB::from((0, 0, 1024, 1024)))
+///     .max_depth(6)
+///     .build();
+/// ```
+///
+/// Unset options keep the values of `QuadTree::default()`.
+#[derive(Debug, Copy, Clone)]
+pub struct QuadTreeBuilder {
+    zone: AABB,
+    max_values: u32,
+    max_depth: u32,
+    order_preserving: bool,
+    edge_inclusive: bool,
+    loose: bool,
+    track_order: bool,
+    grow: bool,
+    min_node_size: Size,
+}
+
+impl QuadTreeBuilder {
+    /// Creates a builder with the options of `QuadTree::default()`
+    pub fn new() -> Self {
+        QuadTreeBuilder::default()
+    }
+
+    /// Zone covered by the tree
+    pub fn zone(mut self, zone: AABB) -> Self {
+        self.zone = zone;
+        self
+    }
+
+    /// Number of values a leaf holds before splitting
+    pub fn max_values(mut self, max_values: u32) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    /// Maximum number of splits from the root to a leaf
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Whether removals keep the order of the remaining values,
+    /// see `QuadTree::order_preserving`
+    pub fn order_preserving(mut self, flag: bool) -> Self {
+        self.order_preserving = flag;
+        self
+    }
+
+    /// Whether a box touching the edges of a quadrant
+    /// fits in that quadrant
+    ///
+    /// When edge-inclusive (the default), a box exactly equal to
+    /// a quadrant descends into that quadrant's child. Otherwise
+    /// a box must be strictly inside a quadrant to descend, so an
+    /// exactly-fitting box stays in the parent, just like a box
+    /// equal to a node's zone can't descend from that node.
+    pub fn edge_inclusive(mut self, flag: bool) -> Self {
+        self.edge_inclusive = flag;
+        self
+    }
+
+    /// Whether the tree is loose
+    ///
+    /// By default a value descends into a child only if it is
+    /// inside that child's zone, so values straddling the center
+    /// of a node stay in that node however small they are. In a
+    /// loose tree, each child also holds the values centered in
+    /// its zone and inside its zone grown by half its size on
+    /// every side: a value descends as long as it is at most
+    /// about the size of the child.
+    ///
+    /// Queries then prune subtrees by their grown zone, which
+    /// overlap each other.
+    pub fn loose(mut self, flag: bool) -> Self {
+        self.loose = flag;
+        self
+    }
+
+    /// Whether values are numbered on insertion,
+    /// see `QuadTree::iter_insertion_order`
+    pub fn track_order(mut self, flag: bool) -> Self {
+        self.track_order = flag;
+        self
+    }
+
+    /// Smallest width and height of the zone of a node,
+    /// 0 (the default) for no limit
+    ///
+    /// A node whose quadrants would be smaller than this does not
+    /// split, and holds any number of values like a node at the
+    /// maximum depth.
+    pub fn min_node_size(mut self, size: Size) -> Self {
+        self.min_node_size = size;
+        self
+    }
+
+    /// Whether the tree grows to hold the values
+    /// inserted out of its zone
+    ///
+    /// By default such values are kept by the root, and are
+    /// visited by every query. A growing tree instead doubles
+    /// the size of its root towards the value, the former root
+    /// becoming one of its quadrants, until the value is inside
+    /// the zone of the tree. The zone is never shrunk back.
+    pub fn grow(mut self, flag: bool) -> Self {
+        self.grow = flag;
+        self
+    }
+
+    /// Creates the configured empty tree
+    ///
+    /// Panics if `max_values` is 0, see `QuadTree::with_bounds`.
+    pub fn build<T: Collidable, M: Default>(self) -> QuadTree<T, M> {
+        let mut qt = QuadTree::with_bounds(self.zone, self.max_values, self.max_depth);
+        qt.order_preserving = self.order_preserving;
+        qt.edge_inclusive = self.edge_inclusive;
+        qt.loose = self.loose;
+        qt.track_order = self.track_order;
+        qt.grow = self.grow;
+        qt.min_node_size = self.min_node_size;
+        qt
+    }
+}
+
+impl Default for QuadTreeBuilder {
+    fn default() -> Self {
+        QuadTreeBuilder {
+            zone: AABB::from((0, 0, 256, 256)),
+            max_values: 1,
+            max_depth: 4,
+            order_preserving: true,
+            edge_inclusive: true,
+            loose: false,
+            track_order: false,
+            grow: false,
+            min_node_size: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{val, Val};
+
+    /// Tree with a single value, exactly equal to its top-left quadrant
+    fn with_quadrant_box(edge_inclusive: bool) -> QuadTree<Val> {
+        let mut qt = QuadTreeBuilder::new()
+            .max_values(1)
+            .edge_inclusive(edge_inclusive)
+            .build();
+        qt.insert(val(200, 200, 4, 4));
+        qt.insert(val(0, 0, 128, 128));
+        qt
+    }
+
+    #[test]
+    fn quadrant_box_descends_when_edge_inclusive() {
+        let qt = with_quadrant_box(true);
+        assert_eq!(
+            qt.drawing_cell(&AABB::from((0, 0, 128, 128))),
+            Some(AABB::from((0, 0, 128, 128)))
+        );
+    }
+
+    #[test]
+    fn quadrant_box_stays_in_parent_when_not_edge_inclusive() {
+        let qt = with_quadrant_box(false);
+        assert_eq!(
+            qt.drawing_cell(&AABB::from((0, 0, 128, 128))),
+            Some(*qt.zone())
+        );
+    }
+
+    #[test]
+    fn build_applies_the_options() {
+        let zone = AABB::from((-64, 0, 512, 128));
+        let qt: QuadTree<Val> = QuadTreeBuilder::new()
+            .zone(zone)
+            .max_values(4)
+            .max_depth(2)
+            .loose(true)
+            .min_node_size(8)
+            .build();
+
+        assert_eq!(*qt.zone(), zone);
+        assert_eq!(qt.max_values(), 4);
+        assert_eq!(qt.max_depth(), 2);
+        assert!(qt.loose);
+        assert_eq!(qt.min_node_size, 8);
+        assert!(qt.edge_inclusive && qt.order_preserving && !qt.grow && !qt.track_order);
+    }
+}
@@ -0,0 +1,154 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::Write;
+
+use super::geometry::{Coord, Size, AABB};
+use super::traits::*;
+use super::QuadTree;
+
+/// Errors raised while loading a tree from CSV
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParseError {
+    /// The line (starting from 1) is not of the form `x,y,w,h`,
+    /// or its box reaches past the range of `Coord`
+    InvalidLine(usize),
+    /// The input holds no box
+    EmptyInput,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidLine(n) => write!(f, "line {} is not of the form x,y,w,h", n),
+            ParseError::EmptyInput => write!(f, "no box to load"),
+        }
+    }
+}
+
+/// Parses a `x,y,w,h` line
+///
+/// Boxes whose right or bottom edge is past `Coord::MAX` are
+/// rejected, so that the zone enclosing them can be computed.
+fn parse_line(line: &str) -> Option<AABB> {
+    let mut fields = line.split(',').map(str::trim);
+    let x: Coord = fields.next()?.parse().ok()?;
+    let y: Coord = fields.next()?.parse().ok()?;
+    let w: Size = fields.next()?.parse().ok()?;
+    let h: Size = fields.next()?.parse().ok()?;
+
+    let edge = |c: Coord, len: Size| Coord::try_from(len).ok()?.checked_add(c);
+    edge(x, w)?;
+    edge(y, h)?;
+
+    match fields.next() {
+        None => Some(AABB { x, y, w, h }),
+        Some(_) => None,
+    }
+}
+
+impl QuadTree<AABB> {
+    /// Builds a tree of boxes from CSV data
+    ///
+    /// Each line holds one box as `x,y,w,h`, blank lines
+    /// are ignored. The zone of the tree is the smallest
+    /// box enclosing every loaded box.
+    ///
+    /// Panics if `max_values` is 0, see `QuadTree::with_bounds`.
+    pub fn from_csv(
+        csv: &str,
+        max_values: u32,
+        max_depth: u32,
+    ) -> Result<QuadTree<AABB>, ParseError> {
+        let mut boxes = Vec::new();
+        let mut zone: Option<AABB> = None;
+        for (i, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let b = parse_line(line).ok_or(ParseError::InvalidLine(i + 1))?;
+            zone = Some(match zone {
+                Some(z) => z.union(&b),
+                None => b,
+            });
+            boxes.push(b);
+        }
+
+        let zone = zone.ok_or(ParseError::EmptyInput)?;
+        let mut qt = QuadTree::with_bounds(zone, max_values, max_depth);
+        for b in boxes {
+            qt.insert(b);
+        }
+        Ok(qt)
+    }
+}
+
+impl<T: Collidable, M: Default> QuadTree<T, M> {
+    /// Exports the leaves as CSV
+    ///
+    /// The first line is the `x,y,w,h,count` header, followed by
+    /// one line per leaf with its zone and its number of values.
+    pub fn leaves_to_csv(&self) -> String {
+        let mut csv = String::from("x,y,w,h,count\n");
+        self.write_leaves(&mut csv);
+        csv
+    }
+
+    fn write_leaves(&self, csv: &mut String) {
+        if self.is_leaf() {
+            let z = &self.zone;
+            let count = self.live_values().count();
+            writeln!(csv, "{},{},{},{},{}", z.x, z.y, z.w, z.h, count).unwrap();
+        }
+        for t in self.children.iter() {
+            t.write_leaves(csv);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_csv_encloses_the_boxes() {
+        let qt = QuadTree::from_csv("10,20,5,5\n\n -30, 0, 10, 100\n", 1, 4).unwrap();
+
+        assert_eq!(qt.len(), 2);
+        assert_eq!(*qt.zone(), AABB::from((-30, 0, 45, 100)));
+        assert_eq!(qt.max_values(), 1);
+        assert_eq!(qt.max_depth(), 4);
+    }
+
+    #[test]
+    fn from_csv_reports_the_invalid_line() {
+        let err = |csv| QuadTree::from_csv(csv, 1, 4).unwrap_err();
+
+        assert_eq!(err("0,0,1,1\n0,0,1\n"), ParseError::InvalidLine(2));
+        assert_eq!(err("0,0,1,1\n\n0,0,1,1,1\n"), ParseError::InvalidLine(3));
+        assert_eq!(err("a,0,1,1\n"), ParseError::InvalidLine(1));
+        assert_eq!(err("0,0,-1,1\n"), ParseError::InvalidLine(1));
+        assert_eq!(err(" \n\n"), ParseError::EmptyInput);
+
+        let overflow = format!("{},0,10,10\n", Coord::MAX - 5);
+        assert_eq!(err(&overflow), ParseError::InvalidLine(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_csv_rejects_empty_nodes() {
+        let _ = QuadTree::from_csv("0,0,1,1\n", 0, 4);
+    }
+
+    #[test]
+    fn leaves_to_csv_has_a_row_per_leaf() {
+        let qt = QuadTree::from_csv("0,0,4,4\n200,200,4,4\n", 1, 4).unwrap();
+        let csv = qt.leaves_to_csv();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("x,y,w,h,count"));
+        let rows: Vec<&str> = lines.collect();
+        assert_eq!(rows.len(), qt.stats().leaf_count);
+        assert_eq!(rows[0], "0,0,102,102,1");
+        assert_eq!(rows[3], "102,102,102,102,1");
+    }
+}
@@ -0,0 +1,35 @@
+use super::traits::*;
+use super::QuadTree;
+
+/// Values redistributed by the splits of a tree,
+/// see `QuadTree::split_distribution_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitReport {
+    /// For each split node: its depth (the root being 0),
+    /// the number of values it held before splitting and
+    /// the number of values it kept after dispatching them
+    /// to its children
+    pub splits: Vec<(u32, usize, usize)>,
+}
+
+impl<T: Collidable, M: Default> QuadTree<T, M> {
+    /// Reports the last split of every split node
+    ///
+    /// Nodes keeping most of their values after splitting hold
+    /// values too big for their children: element sizes may be
+    /// ill-matched to `max_depth`.
+    pub fn split_distribution_report(&self) -> SplitReport {
+        let mut splits = Vec::new();
+        self.collect_splits(0, &mut splits);
+        SplitReport { splits }
+    }
+
+    fn collect_splits(&self, depth: u32, splits: &mut Vec<(u32, usize, usize)>) {
+        if let Some((before, after)) = self.split_record {
+            splits.push((depth, before, after));
+        }
+        for t in self.children.iter() {
+            t.collect_splits(depth + 1, splits);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::convert::TryFrom;
+
+use super::geometry::{Coord, Quadrant, Size, Widen, AABB};
+use super::traits::*;
+use super::QuadTree;
+
+/// Values that can be written as bytes, see `QuadTree::encode`
+pub trait Encode {
+    /// Appends the bytes of `self` to `out`
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// Values that can be read back from bytes, see `QuadTree::decode`
+pub trait Decode: Sized {
+    /// Reads a value from the start of `bytes`,
+    /// advancing `bytes` past the bytes read
+    fn decode(bytes: &mut &[u8]) -> Result<Self, String>;
+}
+
+/// Reads `n` bytes from the start of `bytes`
+fn read<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], String> {
+    if bytes.len() < n {
+        return Err(format!(
+            "unexpected end of input, {} bytes missing",
+            n - bytes.len()
+        ));
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn read_u8(bytes: &mut &[u8]) -> Result<u8, String> {
+    Ok(read(bytes, 1)?[0])
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, String> {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(read(bytes, 4)?);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(bytes: &mut &[u8]) -> Result<u64, String> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(read(bytes, 8)?);
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Boxes are encoded on 64 bits whatever the width of `Coord`
+impl Encode for AABB {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.x.widen().to_le_bytes());
+        out.extend_from_slice(&self.y.widen().to_le_bytes());
+        out.extend_from_slice(&self.w.widen().to_le_bytes());
+        out.extend_from_slice(&self.h.widen().to_le_bytes());
+    }
+}
+
+/// Fails on boxes out of the range of `Coord` and `Size`,
+/// e.g. encoded with the `wide` feature and decoded without
+impl Decode for AABB {
+    fn decode(bytes: &mut &[u8]) -> Result<Self, String> {
+        let coord = |c: u64| {
+            Coord::try_from(c as i64).map_err(|_| format!("coordinate {} out of range", c as i64))
+        };
+        let size = |s: u64| Size::try_from(s).map_err(|_| format!("dimension {} out of range", s));
+        Ok(AABB {
+            x: coord(read_u64(bytes)?)?,
+            y: coord(read_u64(bytes)?)?,
+            w: size(read_u64(bytes)?)?,
+            h: size(read_u64(bytes)?)?,
+        })
+    }
+}
+
+impl<T: Collidable + Encode, M> QuadTree<T, M> {
+    /// Encodes the tree in a compact binary format
+    ///
+    /// The zone and configuration of the tree are followed by
+    /// its nodes, depth-first: each node is encoded as its
+    /// number of values, the values and whether it has children.
+    /// The insertion sequence number of each value is encoded
+    /// along with it only if the tree tracks insertion order,
+    /// see `iter_insertion_order`. Metadata and watched zones
+    /// are not encoded.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.zone.encode(&mut out);
+        out.extend_from_slice(&self.max_values.to_le_bytes());
+        out.extend_from_slice(&self.max_depth.to_le_bytes());
+        out.push(self.order_preserving as u8);
+        out.push(self.edge_inclusive as u8);
+        out.push(self.loose as u8);
+        out.push(self.grow as u8);
+        out.push(self.track_order as u8);
+        out.extend_from_slice(&self.min_node_size.widen().to_le_bytes());
+        out.extend_from_slice(&self.next_seq.to_le_bytes());
+        self.encode_node(&mut out);
+        out
+    }
+
+    fn encode_node(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.live_values().count() as u32).to_le_bytes());
+        for (i, v) in self.values.iter().enumerate() {
+            if self.tombstones[i] {
+                continue;
+            }
+            if self.track_order {
+                out.extend_from_slice(&self.order[i].to_le_bytes());
+            }
+            v.encode(out);
+        }
+
+        out.push(!self.children.is_empty() as u8);
+        for t in self.children.iter() {
+            t.encode_node(out);
+        }
+    }
+}
+
+impl<T: Collidable + Decode, M: Default> QuadTree<T, M> {
+    /// Decodes a tree encoded by `encode`
+    ///
+    /// The structure of the tree is restored as is,
+    /// node metadata being set to `M::default()`.
+    /// Fails on malformed input, e.g. a node holding no value
+    /// or split past the maximum depth.
+    pub fn decode(bytes: &[u8]) -> Result<QuadTree<T, M>, String> {
+        let mut bytes = bytes;
+        let zone = AABB::decode(&mut bytes)?;
+        let max_values = read_u32(&mut bytes)?;
+        let max_depth = read_u32(&mut bytes)?;
+        if max_values == 0 {
+            return Err(String::from("a quadtree node must hold at least one value"));
+        }
+
+        let mut qt = QuadTree::new(max_values, max_depth, zone.x, zone.y, zone.w, zone.h);
+        qt.order_preserving = read_u8(&mut bytes)? != 0;
+        qt.edge_inclusive = read_u8(&mut bytes)? != 0;
+        qt.loose = read_u8(&mut bytes)? != 0;
+        qt.grow = read_u8(&mut bytes)? != 0;
+        qt.track_order = read_u8(&mut bytes)? != 0;
+        let min_node_size = read_u64(&mut bytes)?;
+        qt.min_node_size = Size::try_from(min_node_size)
+            .map_err(|_| format!("minimum node size {} out of range", min_node_size))?;
+        qt.next_seq = read_u64(&mut bytes)?;
+        let qt = qt.decode_nodes(&mut bytes)?;
+
+        if !bytes.is_empty() {
+            return Err(format!("{} trailing bytes", bytes.len()));
+        }
+        Ok(qt)
+    }
+
+    /// Decodes the nodes of the subtree of which this node
+    /// is the root, depth-first
+    ///
+    /// The nodes being decoded are kept on an explicit stack
+    /// rather than recursing, so that deep trees in untrusted
+    /// input don't overflow the call stack.
+    fn decode_nodes(self, bytes: &mut &[u8]) -> Result<QuadTree<T, M>, String> {
+        // Nodes whose children are being decoded, deepest last
+        let mut parents: Vec<QuadTree<T, M>> = Vec::new();
+        let mut node = self;
+
+        loop {
+            for _ in 0..read_u32(bytes)? {
+                let seq = if node.track_order {
+                    read_u64(bytes)?
+                } else {
+                    0
+                };
+                node.push_value(T::decode(bytes)?, seq);
+            }
+
+            if read_u8(bytes)? != 0 {
+                if !node.can_split() {
+                    return Err(String::from(
+                        "node split past the maximum depth or minimum size",
+                    ));
+                }
+                let child = node.new_child(Quadrant::TopLeft);
+                parents.push(node);
+                node = child;
+                continue;
+            }
+
+            // The node is complete: it is attached to its parent,
+            // and decoding goes on with its next sibling
+            loop {
+                let parent = match parents.last_mut() {
+                    Some(parent) => parent,
+                    None => return Ok(node),
+                };
+                parent.children.push(node);
+
+                let n = parent.children.len();
+                if n < 4 {
+                    node = parent.new_child(Quadrant::all()[n]);
+                    break;
+                }
+                node = parents.pop().unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{val, Val};
+
+    impl Encode for Val {
+        fn encode(&self, out: &mut Vec<u8>) {
+            self.bbox.encode(out);
+            out.extend_from_slice(&self.tag.to_le_bytes());
+        }
+    }
+
+    impl Decode for Val {
+        fn decode(bytes: &mut &[u8]) -> Result<Self, String> {
+            let bbox = AABB::decode(bytes)?;
+            let tag = read_u32(bytes)?;
+            Ok(val(bbox.x, bbox.y, bbox.w, bbox.h).tagged(tag))
+        }
+    }
+
+    /// Tree with values held by leaves and by the root,
+    /// over three levels
+    fn sample(track_order: bool) -> QuadTree<Val> {
+        let mut qt: QuadTree<Val> = crate::builder::QuadTreeBuilder::new()
+            .zone(AABB::from((-128, 0, 256, 256)))
+            .track_order(track_order)
+            .build();
+        for (i, &(x, y)) in [(0, 0), (-100, 10), (-120, 20), (100, 200), (-10, 120)]
+            .iter()
+            .enumerate()
+        {
+            qt.insert(val(x, y, 8, 8).tagged(i as u32));
+        }
+        qt
+    }
+
+    /// Zone and tags of every node, depth-first
+    fn shape(qt: &QuadTree<Val>) -> Vec<(AABB, Vec<u32>)> {
+        let mut res = vec![(qt.zone, qt.live_values().map(|v| v.tag).collect())];
+        for t in qt.children.iter() {
+            res.extend(shape(t));
+        }
+        res
+    }
+
+    #[test]
+    fn decode_restores_the_tree() {
+        let qt = sample(false);
+        assert!(qt.depth() >= 2);
+        let back = QuadTree::<Val>::decode(&qt.encode()).unwrap();
+
+        assert_eq!(shape(&back), shape(&qt));
+        assert_eq!(back.max_values, qt.max_values);
+        assert_eq!(back.max_depth, qt.max_depth);
+        assert_eq!(back.encode(), qt.encode());
+    }
+
+    #[test]
+    fn decode_restores_insertion_order() {
+        let mut qt = sample(true);
+        qt.insert(val(60, 60, 200, 8).tagged(5));
+        let mut back = QuadTree::<Val>::decode(&qt.encode()).unwrap();
+
+        let order: Vec<u32> = back.iter_insertion_order().iter().map(|v| v.tag).collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 4, 5]);
+        back.insert(val(0, 0, 1, 1).tagged(6));
+        assert_eq!(back.iter_insertion_order().last().unwrap().tag, 6);
+    }
+
+    #[test]
+    fn decode_rejects_malformed_input() {
+        let bytes = sample(false).encode();
+        let decode = |bytes: &[u8]| QuadTree::<Val>::decode(bytes);
+
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(decode(&trailing).is_err());
+
+        // max_values follows the 32 bytes of the zone
+        let mut empty_nodes = bytes.clone();
+        empty_nodes[32..36].copy_from_slice(&0u32.to_le_bytes());
+        assert!(decode(&empty_nodes).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_deep_splits_without_recursing() {
+        let mut bytes = Vec::new();
+        AABB::from((0, 0, 256, 256)).encode(&mut bytes);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(&[1, 1, 0, 0, 0]);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+
+        // Every first child splits again, until the input ends
+        for _ in 0..100_000 {
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+            bytes.push(1);
+        }
+        assert!(QuadTree::<Val>::decode(&bytes).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+use super::geometry::AABB;
+use super::traits::*;
+use super::QuadTree;
+
+/// A node of a `StaticQuadTree`
+#[derive(Debug)]
+struct StaticNode {
+    zone: AABB,
+    /// Range of the node's values in the tree's values
+    values: Range<usize>,
+    /// Index of the first of the four children, if any
+    children: Option<usize>,
+}
+
+/// An immutable, flattened copy of a `QuadTree`
+///
+/// Nodes are stored breadth-first in a single `Vec`, the
+/// four children of a node being contiguous, and the values
+/// of each node are contiguous in another `Vec`.
+#[derive(Debug)]
+pub struct StaticQuadTree<T: Collidable> {
+    nodes: Vec<StaticNode>,
+    values: Vec<T>,
+}
+
+impl<T: Collidable + Clone> StaticQuadTree<T> {
+    /// Copies the structure and the values of a tree
+    fn snapshot<M>(tree: &QuadTree<T, M>) -> StaticQuadTree<T> {
+        let mut nodes = Vec::new();
+        let mut values = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(tree);
+
+        while let Some(t) = queue.pop_front() {
+            let start = values.len();
+            values.extend(t.live_values().cloned());
+
+            // Children are queued after every node already waiting
+            let children = if t.children.is_empty() {
+                None
+            } else {
+                Some(nodes.len() + queue.len() + 1)
+            };
+            queue.extend(t.children.iter());
+
+            nodes.push(StaticNode {
+                zone: t.bounds(),
+                values: start..values.len(),
+                children,
+            });
+        }
+
+        StaticQuadTree { nodes, values }
+    }
+}
+
+impl<T: Collidable> StaticQuadTree<T> {
+    /// Returns the values intersecting `region`
+    pub fn query_aabb(&self, region: &AABB) -> Vec<&T> {
+        let mut res = Vec::new();
+        let mut stack = vec![0];
+
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            // Values out of the whole tree are kept by the root
+            if i != 0 && !node.zone.intersects(region) {
+                continue;
+            }
+
+            res.extend(
+                self.values[node.values.clone()]
+                    .iter()
+                    .filter(|v| v.bounding_box().intersects(region)),
+            );
+
+            if let Some(c) = node.children {
+                stack.extend(c..c + 4);
+            }
+        }
+        res
+    }
+
+    /// Number of values in the tree
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Tests if the tree holds no value
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Iterates over every value of the tree
+    pub fn values_iter(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+}
+
+/// A shared read-only snapshot of a `QuadTree`
+///
+/// The snapshot is disconnected from the tree it was taken
+/// from: later modifications of the tree do not affect it.
+/// Cloning it is cheap and every clone points to the same
+/// snapshot, so when `T` is `Send + Sync` it can be queried
+/// from several threads at once without locking.
+#[derive(Debug)]
+pub struct FrozenSubtree<T: Collidable> {
+    tree: Arc<StaticQuadTree<T>>,
+}
+
+impl<T: Collidable> Clone for FrozenSubtree<T> {
+    fn clone(&self) -> Self {
+        FrozenSubtree {
+            tree: Arc::clone(&self.tree),
+        }
+    }
+}
+
+impl<T: Collidable> Deref for FrozenSubtree<T> {
+    type Target = StaticQuadTree<T>;
+
+    fn deref(&self) -> &StaticQuadTree<T> {
+        &self.tree
+    }
+}
+
+impl<T: Collidable + Clone, M: Default> QuadTree<T, M> {
+    /// Takes a read-only snapshot of this tree
+    ///
+    /// Every value is cloned.
+    pub fn freeze(&self) -> FrozenSubtree<T> {
+        FrozenSubtree {
+            tree: Arc::new(StaticQuadTree::snapshot(self)),
+        }
+    }
+}
@@ -1,6 +1,186 @@
-extern crate sdl2;
-
+#[cfg(feature = "sdl2")]
 use sdl2::rect::Rect;
+use std::fmt::Debug;
+use std::ops::{Add, Sub};
+
+/// Coordinate type of boxes
+///
+/// `i64` with the `wide` feature, `i32` otherwise.
+#[cfg(not(feature = "wide"))]
+pub type Coord = i32;
+/// Coordinate type of boxes
+///
+/// `i64` with the `wide` feature, `i32` otherwise.
+/// Computations mixing coordinates and dimensions are done
+/// on `i64`, so coordinates should stay within `±2^62`.
+#[cfg(feature = "wide")]
+pub type Coord = i64;
+
+/// Dimension type of boxes
+///
+/// `u64` with the `wide` feature, `u32` otherwise.
+#[cfg(not(feature = "wide"))]
+pub type Size = u32;
+/// Dimension type of boxes
+///
+/// `u64` with the `wide` feature, `u32` otherwise.
+#[cfg(feature = "wide")]
+pub type Size = u64;
+
+/// Lossless conversion of `Coord` and `Size` to their 64 bits
+/// counterpart, for computations mixing both whatever the
+/// `wide` feature
+pub(crate) trait Widen {
+    type Wide;
+
+    fn widen(self) -> Self::Wide;
+}
+
+impl Widen for i32 {
+    type Wide = i64;
+
+    fn widen(self) -> i64 {
+        i64::from(self)
+    }
+}
+
+impl Widen for i64 {
+    type Wide = i64;
+
+    fn widen(self) -> i64 {
+        self
+    }
+}
+
+impl Widen for u32 {
+    type Wide = u64;
+
+    fn widen(self) -> u64 {
+        u64::from(self)
+    }
+}
+
+impl Widen for u64 {
+    type Wide = u64;
+
+    fn widen(self) -> u64 {
+        self
+    }
+}
+
+/// Scalar type of the coordinates of an `AABB`
+///
+/// Positions are of type `Self` and dimensions of type
+/// `Self::Extent`, unsigned for integers.
+pub trait Scalar: Copy + PartialOrd + Debug + Add<Output = Self> + Sub<Output = Self> {
+    /// Dimension type, whose default value is 0
+    type Extent: Copy + PartialOrd + Debug + Default;
+
+    /// `self + e`, saturated for integers so that
+    /// the edges of large boxes don't overflow
+    fn offset(self, e: Self::Extent) -> Self;
+    /// `self - e`, saturated for integers as `offset`
+    fn retreat(self, e: Self::Extent) -> Self;
+    /// `self + e`, `None` if it overflows
+    fn checked_offset(self, e: Self::Extent) -> Option<Self>;
+    /// `self - e`, `None` if it overflows
+    fn checked_retreat(self, e: Self::Extent) -> Option<Self>;
+    /// Extent from `self` to `end`, 0 if `end` is before `self`
+    fn extent_to(self, end: Self) -> Self::Extent;
+    /// Half of `e`, rounded down for integers
+    fn half(e: Self::Extent) -> Self::Extent;
+    /// `e - d`, for `d <= e`
+    fn shrink(e: Self::Extent, d: Self::Extent) -> Self::Extent;
+    fn to_f64(self) -> f64;
+    fn extent_to_f64(e: Self::Extent) -> f64;
+}
+
+macro_rules! int_scalar {
+    ($t:ty, $extent:ty, $wide:ty) => {
+        impl Scalar for $t {
+            type Extent = $extent;
+
+            fn offset(self, e: $extent) -> $t {
+                (self as $wide + e as $wide).min(<$t>::MAX as $wide) as $t
+            }
+            fn retreat(self, e: $extent) -> $t {
+                (self as $wide - e as $wide).max(<$t>::MIN as $wide) as $t
+            }
+            fn checked_offset(self, e: $extent) -> Option<$t> {
+                let c = self as $wide + e as $wide;
+                if c <= <$t>::MAX as $wide {
+                    Some(c as $t)
+                } else {
+                    None
+                }
+            }
+            fn checked_retreat(self, e: $extent) -> Option<$t> {
+                let c = self as $wide - e as $wide;
+                if c >= <$t>::MIN as $wide {
+                    Some(c as $t)
+                } else {
+                    None
+                }
+            }
+            fn extent_to(self, end: $t) -> $extent {
+                (end as $wide - self as $wide).max(0) as $extent
+            }
+            fn half(e: $extent) -> $extent {
+                e / 2
+            }
+            fn shrink(e: $extent, d: $extent) -> $extent {
+                e - d
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn extent_to_f64(e: $extent) -> f64 {
+                e as f64
+            }
+        }
+    };
+}
+
+macro_rules! float_scalar {
+    ($t:ty) => {
+        impl Scalar for $t {
+            type Extent = $t;
+
+            fn offset(self, e: $t) -> $t {
+                self + e
+            }
+            fn retreat(self, e: $t) -> $t {
+                self - e
+            }
+            fn checked_offset(self, e: $t) -> Option<$t> {
+                Some(self + e).filter(|c| c.is_finite())
+            }
+            fn checked_retreat(self, e: $t) -> Option<$t> {
+                Some(self - e).filter(|c| c.is_finite())
+            }
+            fn extent_to(self, end: $t) -> $t {
+                (end - self).max(0.)
+            }
+            fn half(e: $t) -> $t {
+                e / 2.
+            }
+            fn shrink(e: $t, d: $t) -> $t {
+                e - d
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn extent_to_f64(e: $t) -> f64 {
+                e as f64
+            }
+        }
+    };
+}
+
+int_scalar!(i32, u32, i64);
+int_scalar!(i64, u64, i128);
+float_scalar!(f32);
+float_scalar!(f64);
 
 /// Rectangular bounding box
 /// ```text
@@ -10,43 +190,316 @@ use sdl2::rect::Rect;
 /// h |
 ///   ˅
 /// ```
-#[derive(Debug, Copy, Clone)]
-pub struct AABB {
-    pub x: i32,
-    pub y: i32,
-    pub w: u32,
-    pub h: u32,
+///
+/// Boxes are generic over the `Scalar` type of their
+/// coordinates, e.g. `AABBf` for sub-pixel positions.
+/// `AABB` alone uses `Coord`, as `QuadTree` does.
+///
+/// Integer boxes are also `Eq` and `Hash`, e.g. to key maps
+/// by region.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AABB<S: Scalar = Coord> {
+    pub x: S,
+    pub y: S,
+    pub w: S::Extent,
+    pub h: S::Extent,
 }
 
-impl AABB {
+/// Box with `i32` coordinates
+pub type AABBi = AABB<i32>;
+/// Box with `f32` coordinates
+pub type AABBf = AABB<f32>;
+
+impl<S: Scalar> AABB<S> {
     /// Translate the box
-    pub fn translate(&mut self, dx: i32, dy: i32) {
-        self.x += dx;
-        self.y += dy;
+    pub fn translate(&mut self, dx: S, dy: S) {
+        self.x = self.x + dx;
+        self.y = self.y + dy;
     }
 
     /// Inclusion test
     ///
-    /// Tests if this box is inside another one
-    pub fn is_inside(&self, other: AABB) -> bool {
+    /// Tests if this box is inside another one.
+    /// Edges are inclusive: a box sharing an edge with `other`,
+    /// or equal to it, is inside it.
+    pub fn is_inside(&self, other: AABB<S>) -> bool {
         self.x >= other.x
-            && self.x + self.w as i32 <= other.x + other.w as i32
+            && self.x.offset(self.w) <= other.x.offset(other.w)
             && self.y >= other.y
-            && self.y + self.h as i32 <= other.y + other.h as i32
+            && self.y.offset(self.h) <= other.y.offset(other.h)
+    }
+
+    /// Strict inclusion test
+    ///
+    /// Tests if this box is inside another one without
+    /// touching any of its edges: a box equal to `other`,
+    /// or sharing one of its edges, is not inside it.
+    pub fn strictly_inside(&self, other: &AABB<S>) -> bool {
+        self.x > other.x
+            && self.x.offset(self.w) < other.x.offset(other.w)
+            && self.y > other.y
+            && self.y.offset(self.h) < other.y.offset(other.h)
+    }
+
+    /// Intersection test
+    ///
+    /// Tests if this box overlaps another one,
+    /// touching edges count as an overlap, as for `is_inside`.
+    /// Edges are computed with `Scalar::offset`, so that
+    /// large boxes don't overflow.
+    pub fn intersects(&self, other: &AABB<S>) -> bool {
+        self.x <= other.x.offset(other.w)
+            && other.x <= self.x.offset(self.w)
+            && self.y <= other.y.offset(other.h)
+            && other.y <= self.y.offset(self.h)
+    }
+
+    /// Tests if the segment from `(x0, y0)` to `(x1, y1)`
+    /// crosses or touches the box
+    pub fn intersects_segment(&self, x0: S, y0: S, x1: S, y1: S) -> bool {
+        let (ox, oy) = (x0.to_f64(), y0.to_f64());
+        let (dx, dy) = (x1.to_f64() - ox, y1.to_f64() - oy);
+        match self.ray_entry(ox, oy, dx, dy) {
+            Some(t) => t <= 1.,
+            None => false,
+        }
+    }
+
+    /// Rectangle shared by this box and another one,
+    /// `None` if they don't intersect
+    ///
+    /// As for `intersects`, boxes touching by an edge do
+    /// intersect: their intersection has a width or height of 0.
+    pub fn intersection(&self, other: &AABB<S>) -> Option<AABB<S>> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        let max = |a: S, b: S| if b > a { b } else { a };
+        let min = |a: S, b: S| if b < a { b } else { a };
+        let x = max(self.x, other.x);
+        let y = max(self.y, other.y);
+        Some(AABB {
+            x,
+            y,
+            w: x.extent_to(min(self.x.offset(self.w), other.x.offset(other.w))),
+            h: y.extent_to(min(self.y.offset(self.h), other.y.offset(other.h))),
+        })
+    }
+
+    /// Tests if the point `(px, py)` is in the box
+    ///
+    /// Edges are inclusive, as for `intersects`: a point on
+    /// any edge of the box, right and bottom ones included,
+    /// is in the box.
+    pub fn contains_point(&self, px: S, py: S) -> bool {
+        self.x <= px && px <= self.x.offset(self.w) && self.y <= py && py <= self.y.offset(self.h)
+    }
+
+    /// Center of the box, rounded towards its top-left
+    /// corner for integers
+    pub fn center(&self) -> (S, S) {
+        (
+            self.x.offset(S::half(self.w)),
+            self.y.offset(S::half(self.h)),
+        )
+    }
+
+    /// Returns the smallest box containing both
+    /// this box and the point `(px, py)`
+    pub fn expanded_to_include(&self, px: S, py: S) -> AABB<S> {
+        let min = |a: S, b: S| if b < a { b } else { a };
+        let max = |a: S, b: S| if b > a { b } else { a };
+        let x = min(self.x, px);
+        let y = min(self.y, py);
+        AABB {
+            x,
+            y,
+            w: x.extent_to(max(self.x.offset(self.w), px)),
+            h: y.extent_to(max(self.y.offset(self.h), py)),
+        }
+    }
+
+    /// Returns the smallest box containing both boxes
+    pub fn union(&self, other: &AABB<S>) -> AABB<S> {
+        self.expanded_to_include(other.x, other.y)
+            .expanded_to_include(other.x.offset(other.w), other.y.offset(other.h))
+    }
+
+    /// Euclidean distance between the closest points
+    /// of two boxes, 0 if they intersect
+    pub fn distance_to(&self, other: &AABB<S>) -> f64 {
+        let gap = |a: S, aw: S::Extent, b: S, bw: S::Extent| {
+            let (a, b) = (a.to_f64(), b.to_f64());
+            let (aw, bw) = (S::extent_to_f64(aw), S::extent_to_f64(bw));
+            (b - (a + aw)).max(a - (b + bw)).max(0.)
+        };
+        let dx = gap(self.x, self.w, other.x, other.w);
+        let dy = gap(self.y, self.h, other.y, other.h);
+        dx.hypot(dy)
+    }
+
+    /// Corners of the box in counter-clockwise order as seen
+    /// on screen (y pointing down):
+    /// top-left, bottom-left, bottom-right, top-right
+    pub fn to_polygon_vertices(&self) -> [(S, S); 4] {
+        let (r, b) = (self.x.offset(self.w), self.y.offset(self.h));
+        [(self.x, self.y), (self.x, b), (r, b), (r, self.y)]
+    }
+
+    /// Corners of the box in triangle strip order:
+    /// top-left, bottom-left, top-right, bottom-right
+    ///
+    /// Both triangles share the winding of `to_polygon_vertices`.
+    pub fn to_triangle_strip(&self) -> [(S, S); 4] {
+        let (r, b) = (self.x.offset(self.w), self.y.offset(self.h));
+        [(self.x, self.y), (self.x, b), (r, self.y), (r, b)]
+    }
+
+    /// Ray intersection test
+    ///
+    /// Returns the smallest `t >= 0` such that the point
+    /// `(ox + t * dx, oy + t * dy)` is in the box,
+    /// `None` if the ray misses the box.
+    pub fn ray_entry(&self, ox: f64, oy: f64, dx: f64, dy: f64) -> Option<f64> {
+        let mut t_min = 0f64;
+        let mut t_max = f64::INFINITY;
+        let x = self.x.to_f64();
+        let y = self.y.to_f64();
+
+        for &(o, d, lo, hi) in &[
+            (ox, dx, x, x + S::extent_to_f64(self.w)),
+            (oy, dy, y, y + S::extent_to_f64(self.h)),
+        ] {
+            if d == 0. {
+                // Parallel to the slab
+                if o < lo || o > hi {
+                    return None;
+                }
+            } else {
+                let t1 = (lo - o) / d;
+                let t2 = (hi - o) / d;
+                t_min = t_min.max(t1.min(t2));
+                t_max = t_max.min(t1.max(t2));
+            }
+        }
+
+        if t_min <= t_max {
+            Some(t_min)
+        } else {
+            None
+        }
+    }
+}
+
+/// Operations specific to the integer boxes of `QuadTree`
+impl AABB {
+    /// Number of times the box must be split in quadrants
+    /// for the quadrants to be at most `w` by `h`
+    pub fn subdivisions_to_reach(&self, w: Size, h: Size) -> u32 {
+        let (mut cw, mut ch) = (self.w, self.h);
+        let mut n = 0;
+        while cw > w.max(1) || ch > h.max(1) {
+            cw -= cw / 2;
+            ch -= ch / 2;
+            n += 1;
+        }
+        n
+    }
+
+    /// Morton code (Z-order) of the center of the box
+    ///
+    /// Sorting boxes by Morton code keeps boxes close in
+    /// space close in the order. Center coordinates out of
+    /// the `i32` range are clamped.
+    pub fn morton_code(&self) -> u64 {
+        // Maps i32 to u32 keeping the order
+        let to_u32 = |c: i64| {
+            let c = c.clamp(i32::MIN.into(), i32::MAX.into());
+            (c as i32 as u32) ^ 0x8000_0000
+        };
+        // Spreads the bits of a u32 on the even bits of a u64
+        let spread = |v: u32| {
+            let mut v = v as u64;
+            v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+            v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+            v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+            v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+            (v | (v << 1)) & 0x5555_5555_5555_5555
+        };
+
+        let cx = to_u32(self.x.widen() + (self.w / 2).widen() as i64);
+        let cy = to_u32(self.y.widen() + (self.h / 2).widen() as i64);
+        spread(cx) | (spread(cy) << 1)
+    }
+
+    /// Squared Euclidean distance from `(px, py)` to the
+    /// closest point of the box, 0 if the point is in the box
+    ///
+    /// Computed on `i64`, and saturated past `u64::MAX`, so
+    /// that far away points don't overflow.
+    pub fn distance_sq_to_point(&self, px: Coord, py: Coord) -> u64 {
+        let gap = |p: Coord, lo: Coord, len: Size| {
+            let (p, lo) = (p.widen(), lo.widen());
+            (lo - p).max(p - (lo + len.widen() as i64)).max(0) as u64
+        };
+        let dx = gap(px, self.x, self.w);
+        let dy = gap(py, self.y, self.h);
+        dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))
+    }
+
+    /// Area of the box
+    ///
+    /// Computed on `u64` so that large boxes don't overflow,
+    /// saturated past `u64::MAX` with the `wide` feature.
+    pub fn area(&self) -> u64 {
+        self.w.widen().saturating_mul(self.h.widen())
+    }
+
+    /// Linear interpolation between two boxes
+    ///
+    /// Returns `self` for `t = 0` and `other` for `t = 1`,
+    /// coordinates being computed on `f64` and rounded
+    /// once to the closest integer.
+    pub fn lerp(&self, other: &AABB, t: f32) -> AABB {
+        let t = f64::from(t);
+        let mix = |a: f64, b: f64| ((1. - t) * a + t * b).round();
+        AABB {
+            x: mix(self.x as f64, other.x as f64) as Coord,
+            y: mix(self.y as f64, other.y as f64) as Coord,
+            w: mix(self.w as f64, other.w as f64) as Size,
+            h: mix(self.h as f64, other.h as f64) as Size,
+        }
+    }
+
+    /// Graphviz fixed position of the center of the box,
+    /// as `"cx,cy!"`
+    pub fn graphviz_pos(&self) -> String {
+        format!(
+            "{},{}!",
+            self.x.widen() + (self.w / 2).widen() as i64,
+            self.y.widen() + (self.h / 2).widen() as i64
+        )
     }
 }
 
 /// Creates an AABB from a tuple
-impl From<(i32, i32, u32, u32)> for AABB {
-    fn from((x, y, w, h): (i32, i32, u32, u32)) -> AABB {
+impl<S: Scalar> From<(S, S, S::Extent, S::Extent)> for AABB<S> {
+    fn from((x, y, w, h): (S, S, S::Extent, S::Extent)) -> AABB<S> {
         AABB { x, y, w, h }
     }
 }
 
 /// Creates a SDL2 Rect from an AABB
+///
+/// SDL2 rectangles are on 32 bits: with the `wide` feature,
+/// coordinates and dimensions out of range are clamped.
+#[cfg(feature = "sdl2")]
 impl From<&AABB> for Rect {
     fn from(bbox: &AABB) -> Self {
-        Rect::new(bbox.x, bbox.y, bbox.w, bbox.h)
+        let coord = |c: Coord| c.widen().clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+        let size = |s: Size| s.widen().min(u32::MAX.into()) as u32;
+        Rect::new(coord(bbox.x), coord(bbox.y), size(bbox.w), size(bbox.h))
     }
 }
 
@@ -61,34 +514,26 @@ pub enum Quadrant {
 
 impl Quadrant {
     /// Computes the bounding box of a quadrant
-    pub fn quadrant_bbox(bbox: &AABB, q: Quadrant) -> AABB {
+    ///
+    /// The four quadrants exactly tile `bbox`: the left and top
+    /// halves get `w / 2` and `h / 2`, the right and bottom ones
+    /// the remainder, so odd dimensions leave no gap.
+    pub fn quadrant_bbox<S: Scalar>(bbox: &AABB<S>, q: Quadrant) -> AABB<S> {
         use Quadrant::*;
         let z = &bbox;
+        let (lw, th) = (S::half(z.w), S::half(z.h));
+        let (rw, bh) = (S::shrink(z.w, lw), S::shrink(z.h, th));
         AABB::from(match q {
-            TopLeft => (z.x, z.y, (z.w / 2) + (z.w % 2), (z.h / 2) + (z.h % 2)),
-            TopRight => (
-                z.x + (z.w / 2 + 1) as i32,
-                z.y,
-                z.w - (z.w / 2),
-                (z.h / 2) + (z.h % 2),
-            ),
-            BottomLeft => (
-                z.x,
-                z.y + (z.h / 2 + 1) as i32,
-                (z.w / 2) + (z.w % 2),
-                z.h - (z.h / 2),
-            ),
-            BottomRight => (
-                z.x + (z.w / 2 + 1) as i32,
-                z.y + (z.h / 2 + 1) as i32,
-                z.w - (z.w / 2),
-                z.h - (z.h / 2),
-            ),
+            TopLeft => (z.x, z.y, lw, th),
+            TopRight => (z.x.offset(lw), z.y, rw, th),
+            BottomLeft => (z.x, z.y.offset(th), lw, bh),
+            BottomRight => (z.x.offset(lw), z.y.offset(th), rw, bh),
         })
     }
 
-    pub fn all() -> Vec<Quadrant> {
-        vec![
+    /// The four quadrants, in the order of the children of a node
+    pub fn all() -> [Quadrant; 4] {
+        [
             Quadrant::TopLeft,
             Quadrant::TopRight,
             Quadrant::BottomLeft,
@@ -96,3 +541,284 @@ impl Quadrant {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strictly_inside_excludes_edges() {
+        let outer = AABBi::from((0, 0, 10, 10));
+
+        assert!(outer.is_inside(outer));
+        assert!(!outer.strictly_inside(&outer));
+        assert!(AABBi::from((1, 1, 8, 8)).strictly_inside(&outer));
+
+        // Offset by one towards each edge, touching it
+        for &(x, y) in &[(0, 1), (2, 1), (1, 0), (1, 2)] {
+            let b = AABBi::from((x, y, 8, 8));
+            assert!(b.is_inside(outer));
+            assert!(!b.strictly_inside(&outer));
+        }
+
+        // Right edge coinciding with the right edge of `outer`
+        let right = AABBi::from((5, 2, 5, 2));
+        assert!(right.is_inside(outer));
+        assert!(!right.strictly_inside(&outer));
+    }
+
+    #[test]
+    fn strictly_inside_corners_and_empty_boxes() {
+        let outer = AABBi::from((0, 0, 10, 10));
+
+        // Boxes in the corners touch two edges
+        for &(x, y) in &[(0, 0), (8, 0), (0, 8), (8, 8)] {
+            assert!(!AABBi::from((x, y, 2, 2)).strictly_inside(&outer));
+        }
+
+        // An empty box is inside if its point is
+        assert!(AABBi::from((5, 5, 0, 0)).strictly_inside(&outer));
+        assert!(AABBi::from((10, 10, 0, 0)).is_inside(outer));
+        assert!(!AABBi::from((10, 10, 0, 0)).strictly_inside(&outer));
+        assert!(!AABBi::from((0, 5, 0, 0)).strictly_inside(&outer));
+    }
+
+    #[test]
+    fn intersects_disjoint_touching_partial_and_contained() {
+        let a = AABBi::from((0, 0, 10, 10));
+        let check = |b: AABBi, expected: bool| {
+            assert_eq!(a.intersects(&b), expected, "{:?}", b);
+            assert_eq!(b.intersects(&a), expected, "{:?}", b);
+        };
+
+        check(AABBi::from((11, 0, 5, 5)), false);
+        check(AABBi::from((0, -6, 5, 5)), false);
+        check(AABBi::from((10, 3, 5, 5)), true);
+        check(AABBi::from((-5, 10, 5, 5)), true);
+        check(AABBi::from((5, 5, 10, 10)), true);
+        check(AABBi::from((2, 2, 3, 3)), true);
+        check(AABBi::from((-1, -1, 12, 12)), true);
+    }
+
+    #[test]
+    fn intersects_without_overflow() {
+        let wide = AABBi::from((i32::MIN, 0, u32::MAX, 10));
+        assert!(wide.intersects(&AABBi::from((i32::MAX - 1, 5, 1, 1))));
+        assert!(!wide.intersects(&AABBi::from((i32::MAX, 11, 1, 1))));
+    }
+
+    #[test]
+    fn quadrants_tile_odd_and_even_zones() {
+        for &(w, h) in &[(256, 256), (255, 257), (257, 255), (1, 3), (2, 1), (0, 5)] {
+            let z = AABBi::from((-7, 11, w, h));
+            let [tl, tr, bl, br] = Quadrant::all().map(|q| Quadrant::quadrant_bbox(&z, q));
+
+            // Neighbours share an edge, no column or row is lost
+            assert_eq!(tl.x + tl.w as i32, tr.x);
+            assert_eq!(bl.x + bl.w as i32, br.x);
+            assert_eq!(tl.y + tl.h as i32, bl.y);
+            assert_eq!(tr.y + tr.h as i32, br.y);
+            assert_eq!((tl.w + tr.w, bl.w + br.w), (w, w));
+            assert_eq!((tl.h + bl.h, tr.h + br.h), (h, h));
+            assert_eq!(tl.union(&br), z);
+        }
+    }
+
+    #[test]
+    fn quadrants_in_children_order() {
+        use Quadrant::*;
+        let order = Quadrant::all().map(|q| q as usize);
+        assert_eq!(
+            order,
+            [TopLeft, TopRight, BottomLeft, BottomRight].map(|q| q as usize)
+        );
+        assert_eq!(order, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn integer_boxes() {
+        let a = AABBi::from((-10, 0, 21, 10));
+        let b = AABBi::from((5, 5, 10, 10));
+
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(AABBi::from((5, 5, 6, 5))));
+        assert_eq!(a.union(&b), AABBi::from((-10, 0, 25, 15)));
+        assert_eq!(a.center(), (0, 5));
+        assert!(a.contains_point(11, 10));
+        assert_eq!(
+            Quadrant::quadrant_bbox(&a, Quadrant::BottomRight),
+            AABBi::from((0, 5, 11, 5))
+        );
+    }
+
+    #[test]
+    fn float_boxes() {
+        let a = AABBf::from((-10., 0., 21., 10.));
+        let b = AABBf::from((5.5, 5., 10., 10.));
+
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(AABBf::from((5.5, 5., 5.5, 5.))));
+        assert_eq!(a.union(&b), AABBf::from((-10., 0., 25.5, 15.)));
+        assert_eq!(a.center(), (0.5, 5.));
+        assert!(a.contains_point(10.75, 10.));
+        assert!(!a.contains_point(11.25, 10.));
+        assert_eq!(
+            Quadrant::quadrant_bbox(&a, Quadrant::BottomRight),
+            AABBf::from((0.5, 5., 10.5, 5.))
+        );
+        assert_eq!(a.distance_to(&AABBf::from((14., 14., 1., 1.))), 5.);
+    }
+
+    #[test]
+    fn center_rounds_towards_the_top_left() {
+        assert_eq!(AABBi::from((0, 0, 10, 20)).center(), (5, 10));
+        assert_eq!(AABBi::from((0, 0, 11, 21)).center(), (5, 10));
+        assert_eq!(AABBi::from((-11, -21, 11, 21)).center(), (-6, -11));
+        assert_eq!(AABBi::from((3, 4, 0, 1)).center(), (3, 4));
+    }
+
+    #[test]
+    fn area_of_even_odd_and_max_size_boxes() {
+        assert_eq!(AABB::from((0, 0, 10, 20)).area(), 200);
+        assert_eq!(AABB::from((-5, 7, 11, 21)).area(), 231);
+        assert_eq!(AABB::from((0, 0, 0, 21)).area(), 0);
+
+        let max = AABB::from((Coord::MIN, Coord::MIN, Size::MAX, Size::MAX));
+        let side = u64::from(u32::MAX);
+        #[cfg(not(feature = "wide"))]
+        assert_eq!(max.area(), side * side);
+        #[cfg(feature = "wide")]
+        assert_eq!(max.area(), u64::MAX);
+        assert!(max.area() >= side * side);
+    }
+
+    #[test]
+    fn intersection_of_overlapping_touching_and_disjoint_boxes() {
+        let a = AABBi::from((0, 0, 10, 10));
+
+        assert_eq!(
+            a.intersection(&AABBi::from((6, -4, 10, 8))),
+            Some(AABBi::from((6, 0, 4, 4)))
+        );
+        assert_eq!(
+            a.intersection(&AABBi::from((2, 3, 4, 5))),
+            Some(AABBi::from((2, 3, 4, 5)))
+        );
+        // Touching boxes share a line
+        assert_eq!(
+            a.intersection(&AABBi::from((10, 2, 5, 5))),
+            Some(AABBi::from((10, 2, 0, 5)))
+        );
+        assert_eq!(
+            a.intersection(&AABBi::from((10, 10, 5, 5))),
+            Some(AABBi::from((10, 10, 0, 0)))
+        );
+        // A near miss does not underflow
+        assert_eq!(a.intersection(&AABBi::from((11, 0, 5, 5))), None);
+        assert_eq!(a.intersection(&AABBi::from((-20, -20, 5, 5))), None);
+    }
+
+    #[test]
+    fn union_of_disjoint_and_nested_boxes() {
+        let a = AABBi::from((0, 0, 10, 10));
+
+        assert_eq!(
+            a.union(&AABBi::from((20, -5, 5, 5))),
+            AABBi::from((0, -5, 25, 15))
+        );
+        assert_eq!(a.union(&AABBi::from((2, 2, 3, 3))), a);
+        assert_eq!(AABBi::from((2, 2, 3, 3)).union(&a), a);
+    }
+
+    #[test]
+    fn equal_boxes_hash_alike() {
+        use std::collections::HashSet;
+
+        let set: HashSet<AABBi> = [
+            AABBi::from((0, 0, 10, 10)),
+            AABBi::from((0, 0, 10, 10)),
+            AABBi::from((0, 0, 10, 11)),
+            AABBi::from((1, 0, 10, 10)),
+            AABBi::from((1, 0, 10, 10)),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&AABBi::from((0, 0, 10, 11))));
+    }
+
+    #[test]
+    fn distance_sq_to_points_inside_beside_and_off_a_corner() {
+        let b = AABB::from((10, 20, 30, 40));
+
+        assert_eq!(b.distance_sq_to_point(25, 30), 0);
+        assert_eq!(b.distance_sq_to_point(40, 60), 0);
+        assert_eq!(b.distance_sq_to_point(4, 30), 36);
+        assert_eq!(b.distance_sq_to_point(25, 63), 9);
+        assert_eq!(b.distance_sq_to_point(43, 64), 9 + 16);
+        assert_eq!(b.distance_sq_to_point(7, 16), 9 + 16);
+    }
+
+    #[test]
+    fn lerp_rounds_once() {
+        let a = AABB::from((0, 0, 10, 10));
+        let b = AABB::from((100, -50, 20, 11));
+
+        assert_eq!(a.lerp(&b, 0.), a);
+        assert_eq!(a.lerp(&b, 1.), b);
+        assert_eq!(a.lerp(&b, 0.5), AABB::from((50, -25, 15, 11)));
+        assert_eq!(a.lerp(&b, 0.25), AABB::from((25, -13, 13, 10)));
+    }
+
+    #[test]
+    fn lerp_is_exact_far_from_the_origin() {
+        let a = AABB::from((16_777_217, 0, 2, 2));
+        let b = AABB::from((16_777_219, 0, 2, 2));
+        assert_eq!(a.lerp(&b, 0.5), AABB::from((16_777_218, 0, 2, 2)));
+    }
+
+    #[test]
+    fn expanded_to_include_each_edge() {
+        let b = AABBi::from((10, 10, 10, 10));
+
+        assert_eq!(b.expanded_to_include(5, 15), AABBi::from((5, 10, 15, 10)));
+        assert_eq!(b.expanded_to_include(25, 15), AABBi::from((10, 10, 15, 10)));
+        assert_eq!(b.expanded_to_include(15, 5), AABBi::from((10, 5, 10, 15)));
+        assert_eq!(b.expanded_to_include(15, 25), AABBi::from((10, 10, 10, 15)));
+        assert_eq!(b.expanded_to_include(0, 30), AABBi::from((0, 10, 20, 20)));
+        assert_eq!(b.expanded_to_include(15, 15), b);
+    }
+
+    #[test]
+    fn polygon_vertices_wind_counter_clockwise_on_screen() {
+        assert_eq!(
+            AABBi::from((10, 20, 30, 40)).to_polygon_vertices(),
+            [(10, 20), (10, 60), (40, 60), (40, 20)]
+        );
+        assert_eq!(
+            AABBf::from((-1., 0.5, 2., 1.)).to_polygon_vertices(),
+            [(-1., 0.5), (-1., 1.5), (1., 1.5), (1., 0.5)]
+        );
+    }
+
+    #[test]
+    fn triangle_strip_order() {
+        let b = AABBi::from((10, 20, 30, 40));
+        let [tl, bl, br, tr] = b.to_polygon_vertices();
+
+        assert_eq!(
+            b.to_triangle_strip(),
+            [(10, 20), (10, 60), (40, 20), (40, 60)]
+        );
+        assert_eq!(b.to_triangle_strip(), [tl, bl, tr, br]);
+    }
+
+    #[test]
+    #[cfg(feature = "wide")]
+    fn morton_code_clamps_far_centers() {
+        let far = AABB::from((i64::MAX / 2, i64::MIN / 2, 2, 2));
+        let edge = AABB::from((i64::from(i32::MAX), i64::from(i32::MIN), 0, 0));
+        assert_eq!(far.morton_code(), edge.morton_code());
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter::Zip;
+use std::slice;
+
+use super::geometry::{Coord, Scalar};
+use super::traits::*;
+use super::QuadTree;
+
+/// Iterator over the values of a subtree
+///
+/// Nodes are visited depth-first using an explicit stack,
+/// the values of a node being yielded before its children's.
+pub struct Iter<'a, T: Collidable<S>, M, S: Scalar = Coord> {
+    /// Nodes left to visit
+    nodes: Vec<&'a QuadTree<T, M, S>>,
+    /// Values left in the current node, with their deletion flag
+    values: Zip<slice::Iter<'a, T>, slice::Iter<'a, bool>>,
+}
+
+impl<'a, S: Scalar, T: Collidable<S>, M> Iter<'a, T, M, S> {
+    pub(super) fn new(root: &'a QuadTree<T, M, S>) -> Self {
+        Iter {
+            nodes: vec![root],
+            values: [].iter().zip([].iter()),
+        }
+    }
+}
+
+impl<'a, S: Scalar, T: Collidable<S>, M> Iterator for Iter<'a, T, M, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some((v, _)) = self.values.find(|(_, &deleted)| !deleted) {
+                return Some(v);
+            }
+
+            let t = self.nodes.pop()?;
+            self.values = t.values.iter().zip(t.tombstones.iter());
+            self.nodes.extend(t.children.iter().rev());
+        }
+    }
+}
+
+/// Mutable iterator over the values of a subtree,
+/// see `QuadTree::iter_mut`
+///
+/// Nodes are visited in the same order as `Iter`. Every
+/// visited node is flagged for `QuadTree::reindex_moved`.
+pub struct IterMut<'a, T: Collidable<S>, M, S: Scalar = Coord> {
+    /// Nodes left to visit
+    nodes: Vec<&'a mut QuadTree<T, M, S>>,
+    /// Values left in the current node, with their deletion flag
+    values: Zip<slice::IterMut<'a, T>, slice::Iter<'a, bool>>,
+}
+
+impl<'a, S: Scalar, T: Collidable<S>, M> IterMut<'a, T, M, S> {
+    pub(super) fn new(root: &'a mut QuadTree<T, M, S>) -> Self {
+        IterMut {
+            nodes: vec![root],
+            values: [].iter_mut().zip([].iter()),
+        }
+    }
+}
+
+impl<'a, S: Scalar, T: Collidable<S>, M> Iterator for IterMut<'a, T, M, S> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        loop {
+            if let Some((v, _)) = self.values.find(|(_, &deleted)| !deleted) {
+                return Some(v);
+            }
+
+            let t = self.nodes.pop()?;
+            t.stale = true;
+            t.centroid = None;
+
+            // Borrowing the fields separately so that the values
+            // and the children can be handed out together
+            let QuadTree {
+                values,
+                tombstones,
+                children,
+                ..
+            } = t;
+            let tombstones: &'a Vec<bool> = tombstones;
+            self.values = values.iter_mut().zip(tombstones.iter());
+            self.nodes.extend(children.iter_mut().rev());
+        }
+    }
+}
+
+/// Lazy iterator over the pairs of values whose bounding
+/// boxes intersect, see `QuadTree::iter_overlapping_pairs`
+///
+/// Nodes are visited depth-first. Each value `a` is checked
+/// against the values following it in its own node and against
+/// the values of the nodes left to visit, subtrees whose bounds
+/// do not intersect `a` being skipped. Pairs involving the value
+/// of a node visited earlier are produced when visiting that
+/// node, so every pair is produced exactly once, including pairs
+/// of values touching across the edge of sibling zones.
+pub struct OverlappingPairs<'a, T: Collidable, M> {
+    /// Nodes left to visit
+    nodes: Vec<&'a QuadTree<T, M>>,
+    /// Node holding the current value
+    node: Option<&'a QuadTree<T, M>>,
+    /// Index of the current value in its node
+    index: usize,
+    /// Subtrees left to check against the current value
+    descendants: Vec<&'a QuadTree<T, M>>,
+    /// Values left to check against the current value,
+    /// with their deletion flag
+    candidates: Zip<slice::Iter<'a, T>, slice::Iter<'a, bool>>,
+}
+
+impl<'a, T: Collidable, M> OverlappingPairs<'a, T, M> {
+    pub(super) fn new(root: &'a QuadTree<T, M>) -> Self {
+        let mut pairs = OverlappingPairs {
+            nodes: root.children.iter().collect(),
+            node: Some(root),
+            index: 0,
+            descendants: Vec::new(),
+            candidates: [].iter().zip([].iter()),
+        };
+        pairs.start_value();
+        pairs
+    }
+
+    /// Resets the candidates for the current value
+    fn start_value(&mut self) {
+        if let Some(node) = self.node {
+            let next = (self.index + 1).min(node.values.len());
+            self.candidates = node.values[next..]
+                .iter()
+                .zip(node.tombstones[next..].iter());
+            self.descendants.clear();
+            self.descendants.extend(self.nodes.iter());
+        }
+    }
+}
+
+impl<'a, T: Collidable, M> Iterator for OverlappingPairs<'a, T, M> {
+    type Item = (&'a T, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.node?;
+
+            match node.values.get(self.index) {
+                Some(_) if node.tombstones[self.index] => {
+                    self.index += 1;
+                    self.start_value();
+                }
+                Some(a) => {
+                    let bbox = a.bounding_box();
+                    if let Some((b, _)) = self
+                        .candidates
+                        .find(|(b, &deleted)| !deleted && b.bounding_box().intersects(bbox))
+                    {
+                        return Some((a, b));
+                    }
+
+                    match self.descendants.pop() {
+                        Some(t) => {
+                            if t.bounds().intersects(bbox) {
+                                self.candidates = t.values.iter().zip(t.tombstones.iter());
+                                self.descendants.extend(t.children.iter());
+                            }
+                        }
+                        None => {
+                            self.index += 1;
+                            self.start_value();
+                        }
+                    }
+                }
+                None => {
+                    // Every value of the node has been checked
+                    self.node = self.nodes.pop();
+                    if let Some(t) = self.node {
+                        self.nodes.extend(t.children.iter());
+                    }
+                    self.index = 0;
+                    self.start_value();
+                }
+            }
+        }
+    }
+}
+
+/// An element waiting in a `Raycast` queue
+enum RayHit<'a, T: Collidable, M> {
+    Node(&'a QuadTree<T, M>),
+    Value(&'a T),
+}
+
+/// A `RayHit` with its distance along the ray,
+/// ordered so that the closest one is the greatest
+struct RayEntry<'a, T: Collidable, M> {
+    t: f64,
+    hit: RayHit<'a, T, M>,
+}
+
+impl<'a, T: Collidable, M> PartialEq for RayEntry<'a, T, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, T: Collidable, M> Eq for RayEntry<'a, T, M> {}
+
+impl<'a, T: Collidable, M> PartialOrd for RayEntry<'a, T, M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T: Collidable, M> Ord for RayEntry<'a, T, M> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.t.partial_cmp(&self.t).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Lazy iterator over the values hit by a ray,
+/// see `QuadTree::raycast_iter`
+///
+/// Nodes and values are kept in a priority queue ordered by
+/// the distance at which the ray enters them: a node is only
+/// opened when nothing closer is left in the queue.
+pub struct Raycast<'a, T: Collidable, M> {
+    ray: (f64, f64, f64, f64),
+    queue: BinaryHeap<RayEntry<'a, T, M>>,
+}
+
+impl<'a, T: Collidable, M> Raycast<'a, T, M> {
+    pub(super) fn new(root: &'a QuadTree<T, M>, ox: f64, oy: f64, dx: f64, dy: f64) -> Self {
+        let mut ray = Raycast {
+            ray: (ox, oy, dx, dy),
+            queue: BinaryHeap::new(),
+        };
+        // Values out of the whole tree are kept by the root,
+        // so the root is opened whatever its zone
+        ray.queue.push(RayEntry {
+            t: 0.,
+            hit: RayHit::Node(root),
+        });
+        ray
+    }
+
+    fn push_node(&mut self, t: &'a QuadTree<T, M>) {
+        let (ox, oy, dx, dy) = self.ray;
+        if let Some(d) = t.bounds().ray_entry(ox, oy, dx, dy) {
+            self.queue.push(RayEntry {
+                t: d,
+                hit: RayHit::Node(t),
+            });
+        }
+    }
+}
+
+impl<'a, T: Collidable, M> Iterator for Raycast<'a, T, M> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let (ox, oy, dx, dy) = self.ray;
+
+        while let Some(entry) = self.queue.pop() {
+            match entry.hit {
+                RayHit::Value(v) => return Some(v),
+                RayHit::Node(t) => {
+                    for v in t.live_values() {
+                        if let Some(d) = v.bounding_box().ray_entry(ox, oy, dx, dy) {
+                            self.queue.push(RayEntry {
+                                t: d,
+                                hit: RayHit::Value(v),
+                            });
+                        }
+                    }
+                    for c in t.children.iter() {
+                        self.push_node(c);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
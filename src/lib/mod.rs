@@ -1,18 +1,58 @@
-extern crate sdl2;
-
+pub mod builder;
+pub mod csv;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod encode;
+pub mod frozen;
 pub mod geometry;
+pub mod iter;
+pub mod stats;
+pub mod tracking;
 pub mod traits;
+pub mod watch;
+
+#[cfg(test)]
+mod tests;
 
-use rand::Rng;
+#[cfg(feature = "sdl2")]
 use sdl2::pixels::Color;
+#[cfg(feature = "sdl2")]
 use sdl2::rect::Rect;
-use std::collections::LinkedList;
+#[cfg(feature = "sdl2")]
+use sdl2::render::{Canvas, RenderTarget};
+use std::fmt::Write;
+use std::iter::FromIterator;
 use std::time::Duration;
 
-use geometry::{Quadrant, AABB};
+use builder::QuadTreeBuilder;
+use geometry::{Coord, Quadrant, Scalar, Size, Widen, AABB};
+use iter::{Iter, IterMut, OverlappingPairs, Raycast};
+use tracking::QuadTreeWithId;
 use traits::*;
+use watch::{WatchHandle, Watches, ZoneEvent};
+
+/// Depth at which the leaves of a tree covering `bounds`
+/// are about the size of `typical_size`, so that most objects
+/// of that size land in leaves
+///
+/// Meant as the `max_depth` of such a tree.
+pub fn recommended_depth(bounds: &AABB, typical_size: (Size, Size)) -> u32 {
+    let (w, h) = typical_size;
+    bounds.subdivisions_to_reach(w, h)
+}
 
-type WinCanvas = sdl2::render::Canvas<sdl2::video::Window>;
+/// Loose bounds of a node of zone `zone`: the zone
+/// grown by half its size on every side
+fn loose_bounds<S: Scalar>(zone: &AABB<S>) -> AABB<S> {
+    let (hw, hh) = (S::half(zone.w), S::half(zone.h));
+    let (x, y) = (zone.x.retreat(hw), zone.y.retreat(hh));
+    AABB {
+        x,
+        y,
+        w: x.extent_to(zone.x.offset(zone.w).offset(hw)),
+        h: y.extent_to(zone.y.offset(zone.h).offset(hh)),
+    }
+}
 
 /// A structure representing a quadtree
 ///
@@ -24,34 +64,88 @@ type WinCanvas = sdl2::render::Canvas<sdl2::video::Window>;
 /// An object not fitting on a leaf of the quadtree will
 /// be kept in the closest big-enough parent.
 ///
+/// Objects are placed by their `AABB`, whose coordinates are
+/// of the `Scalar` type `S`, `Coord` by default: e.g. a
+/// `QuadTree<T, (), f32>` indexes values with `AABBf` boxes
+/// for sub-pixel positions.
+///
 /// Usage:
-/// ```
+/// ```ignore
 ///let qt = QuadTree::<Obj>::default();
 /// qt.insert(obj1);
 /// ```
 ///
-/// Use only with even dimensions.
+/// Each node can also hold some user metadata of type `M`
+/// (e.g. the kind of terrain of a region), see `node_meta_mut`.
 ///
-/// *Todo: handle odd dimensions too*
-#[derive(Debug)]
-pub struct QuadTree<T: Collidable> {
-    zone: AABB,
+/// Cloning a tree deep-copies its nodes, values and metadata,
+/// e.g. to snapshot a state and roll back to it.
+#[derive(Debug, Clone)]
+pub struct QuadTree<T: Collidable<S>, M = (), S: Scalar = Coord> {
+    zone: AABB<S>,
     max_values: u32,
     max_depth: u32,
-    children: Vec<QuadTree<T>>,
+    order_preserving: bool,
+    edge_inclusive: bool,
+    loose: bool,
+    track_order: bool,
+    grow: bool,
+    /// Smallest width and height of the zones of children
+    min_node_size: S::Extent,
+    children: Vec<QuadTree<T, M, S>>,
     values: Vec<T>,
+    /// Whether each value is logically deleted, see `mark_deleted`
+    tombstones: Vec<bool>,
+    /// Insertion sequence number of each value, see `iter_insertion_order`
+    order: Vec<u64>,
+    /// Sequence number of the next inserted value, only used by the root
+    next_seq: u64,
+    /// Zones watched by `update_watched`, only used by the root
+    watches: Watches<S>,
+    /// Average center of the subtree's values, see `build_centroid_cache`
+    centroid: Option<(f64, f64)>,
+    /// Whether the subtree holds values moved by `step`
+    /// or `iter_mut` which are not re-homed yet
+    stale: bool,
+    /// Values held before and after the last split of this node
+    #[cfg(feature = "diagnostics")]
+    split_record: Option<(usize, usize)>,
+    meta: M,
 }
 
-impl<T: Collidable> QuadTree<T> {
+/// Operations available whatever the scalar type
+impl<S: Scalar, T: Collidable<S>, M: Default> QuadTree<T, M, S> {
     /// Creates a new QuadTree
     /// with given arguments
-    fn new(max_values: u32, max_depth: u32, x: i32, y: i32, w: u32, h: u32) -> QuadTree<T> {
+    fn new(
+        max_values: u32,
+        max_depth: u32,
+        x: S,
+        y: S,
+        w: S::Extent,
+        h: S::Extent,
+    ) -> QuadTree<T, M, S> {
         QuadTree {
             max_values,
             max_depth,
+            order_preserving: true,
+            edge_inclusive: true,
+            loose: false,
+            track_order: false,
+            grow: false,
+            min_node_size: S::Extent::default(),
             zone: AABB { x, y, w, h },
             children: Vec::default(),
             values: Vec::default(),
+            tombstones: Vec::default(),
+            order: Vec::default(),
+            next_seq: 0,
+            watches: Watches::default(),
+            centroid: None,
+            stale: false,
+            #[cfg(feature = "diagnostics")]
+            split_record: None,
+            meta: M::default(),
         }
     }
 
@@ -59,146 +153,2052 @@ impl<T: Collidable> QuadTree<T> {
     ///
     /// A child has depth - 1 compared to its parent
     /// and is focused on one of the four quadrants
-    fn new_child(&self, q: Quadrant) -> QuadTree<T> {
-        QuadTree::<T> {
+    fn new_child(&self, q: Quadrant) -> QuadTree<T, M, S> {
+        QuadTree::<T, M, S> {
             zone: Quadrant::quadrant_bbox(&self.zone, q),
             max_depth: self.max_depth - 1,
             max_values: self.max_values,
-            children: Vec::<QuadTree<T>>::default(),
+            order_preserving: self.order_preserving,
+            edge_inclusive: self.edge_inclusive,
+            loose: self.loose,
+            track_order: self.track_order,
+            grow: self.grow,
+            min_node_size: self.min_node_size,
+            children: Vec::<QuadTree<T, M, S>>::default(),
             values: Vec::<T>::default(),
+            tombstones: Vec::default(),
+            order: Vec::default(),
+            next_seq: 0,
+            watches: Watches::default(),
+            centroid: None,
+            stale: false,
+            #[cfg(feature = "diagnostics")]
+            split_record: None,
+            meta: M::default(),
         }
     }
 
+    /// Creates an empty tree covering `zone`
+    ///
+    /// A leaf splits when it holds more than `max_values`
+    /// values, at most `max_depth` times from the root:
+    /// with a `max_depth` of 0 the root is a leaf that never
+    /// splits.
+    ///
+    /// Panics if `max_values` is 0.
+    pub fn with_bounds(zone: AABB<S>, max_values: u32, max_depth: u32) -> QuadTree<T, M, S> {
+        assert!(
+            max_values >= 1,
+            "a quadtree node must hold at least one value"
+        );
+        QuadTree::new(max_values, max_depth, zone.x, zone.y, zone.w, zone.h)
+    }
+
+    /// Creates an empty tree with the same zone
+    /// and configuration as this one
+    pub fn empty_like(&self) -> QuadTree<T, M, S> {
+        let z = &self.zone;
+        let mut qt = QuadTree::new(self.max_values, self.max_depth, z.x, z.y, z.w, z.h);
+        qt.order_preserving = self.order_preserving;
+        qt.edge_inclusive = self.edge_inclusive;
+        qt.loose = self.loose;
+        qt.track_order = self.track_order;
+        qt.grow = self.grow;
+        qt.min_node_size = self.min_node_size;
+        qt
+    }
+
     pub fn is_leaf(&self) -> bool {
         self.children.is_empty()
     }
 
+    /// Iterates over every value of the tree
+    ///
+    /// Values of a node are yielded before the values of its
+    /// children, each value exactly once.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        Iter::new(self)
+    }
+
+    /// Iterates mutably over every value of the tree
+    ///
+    /// Bounding boxes changed through this iterator are not
+    /// re-homed and queries may miss the values which left
+    /// their node: call `reindex_moved` afterwards.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        IterMut::new(self)
+    }
+
+    /// Number of values of the tree
+    ///
+    /// Every node is visited, so this costs `O(n)`.
+    pub fn len(&self) -> usize {
+        self.live_values().count() + self.children.iter().map(|t| t.len()).sum::<usize>()
+    }
+
+    /// Whether the tree holds no value
+    pub fn is_empty(&self) -> bool {
+        self.live_values().next().is_none() && self.children.iter().all(|t| t.is_empty())
+    }
+
+    /// Smallest box holding every value of the tree,
+    /// `None` if the tree is empty
+    pub fn bounding_box_of(&self) -> Option<AABB<S>> {
+        self.iter()
+            .map(|v| *v.bounding_box())
+            .fold(None, |acc, b| match acc {
+                Some(acc) => Some(b.union(&acc)),
+                None => Some(b),
+            })
+    }
+
     /// Checks if a values fits in one of the current node.zone quadrants
     /// Returns `Some(Quadrant)` if it does, `None` otherwise
     fn fits(&self, v: &T) -> Option<Quadrant> {
-        for q in Quadrant::all() {
-            if v.bounding_box()
-                .is_inside(Quadrant::quadrant_bbox(&self.zone, q))
-            {
-                return Some(q);
-            }
+        self.fits_box(v.bounding_box())
+    }
+
+    /// Checks if a box fits in one of the current node.zone quadrants
+    ///
+    /// In loose trees, a box fits in the loose bounds of the
+    /// quadrant holding its center, see `QuadTreeBuilder::loose`.
+    fn fits_box(&self, bbox: &AABB<S>) -> Option<Quadrant> {
+        if self.loose {
+            let z = &self.zone;
+            let (cx, cy) = bbox.center();
+            let (zx, zy) = z.center();
+            let right = cx >= zx;
+            let bottom = cy >= zy;
+            let q = Quadrant::all()[right as usize + 2 * bottom as usize];
+            let zone = loose_bounds(&Quadrant::quadrant_bbox(z, q));
+            return if self.holds(bbox, zone) {
+                Some(q)
+            } else {
+                None
+            };
+        }
+
+        Quadrant::all()
+            .iter()
+            .copied()
+            .find(|&q| self.holds(bbox, Quadrant::quadrant_bbox(&self.zone, q)))
+    }
+
+    /// Checks if a box is placed in this node's subtree
+    /// when inserted from its parent
+    ///
+    /// In loose trees, this requires the center of the box to
+    /// be in the node's zone, right and bottom edges excluded
+    /// as when choosing a quadrant in `fits_box`.
+    fn belongs(&self, bbox: &AABB<S>) -> bool {
+        if !self.loose {
+            return self.holds(bbox, self.zone);
+        }
+
+        let z = &self.zone;
+        let (cx, cy) = bbox.center();
+        z.x <= cx
+            && cx < z.x.offset(z.w)
+            && z.y <= cy
+            && cy < z.y.offset(z.h)
+            && self.holds(bbox, self.bounds())
+    }
+
+    /// Checks if a box is inside a zone, touching the zone's
+    /// edges counting as inside only if the tree is edge-inclusive
+    fn holds(&self, bbox: &AABB<S>, zone: AABB<S>) -> bool {
+        if self.edge_inclusive {
+            bbox.is_inside(zone)
+        } else {
+            bbox.strictly_inside(&zone)
         }
-        return None;
     }
 
     /// Correctly insert a new value in a quadtree
     pub fn insert(&mut self, v: T) {
+        let seq = self.new_seq();
+        self.watches.inserted(v.bounding_box());
+        self.insert_root_entry(v, seq);
+    }
+
+    /// Sequence number of a newly inserted value,
+    /// 0 if insertion order is not tracked
+    fn new_seq(&mut self) -> u64 {
+        if !self.track_order {
+            return 0;
+        }
+        self.next_seq += 1;
+        self.next_seq - 1
+    }
+
+    /// Inserts a value in the tree, of which this node is
+    /// the root, keeping its sequence number
+    ///
+    /// In growing trees, the root is first grown until it
+    /// holds the value, see `QuadTreeBuilder::grow`.
+    pub(crate) fn insert_root_entry(&mut self, v: T, seq: u64) {
+        if self.grow {
+            let bbox = *v.bounding_box();
+            while !self.holds(&bbox, self.zone) && self.grow_towards(&bbox) {}
+        }
+        self.insert_entry(v, seq);
+    }
+
+    /// Doubles the size of the root towards `bbox`, the
+    /// former root becoming one of the quadrants of the new one
+    ///
+    /// The root grows left if `bbox` is left of its zone,
+    /// right otherwise, and likewise up or down. Returns
+    /// `false` if the new zone would overflow `S`, or
+    /// if the zone has no width or height and cannot grow.
+    fn grow_towards(&mut self, bbox: &AABB<S>) -> bool {
+        let z = self.zone;
+        if z.w == S::Extent::default() || z.h == S::Extent::default() {
+            return false;
+        }
+        let left = bbox.x < z.x;
+        let up = bbox.y < z.y;
+
+        // Start of the new zone towards the box, and the
+        // size of the old zone twice from there
+        let span = |start: Option<S>, e: S::Extent| {
+            let start = start?;
+            let end = start.checked_offset(e)?.checked_offset(e)?;
+            Some((start, start.extent_to(end)))
+        };
+        let x = if left {
+            z.x.checked_retreat(z.w)
+        } else {
+            Some(z.x)
+        };
+        let y = if up {
+            z.y.checked_retreat(z.h)
+        } else {
+            Some(z.y)
+        };
+        let ((x, w), (y, h)) = match (span(x, z.w), span(y, z.h)) {
+            (Some(sx), Some(sy)) => (sx, sy),
+            _ => return false,
+        };
+
+        let mut root = QuadTree::new(self.max_values, self.max_depth + 1, x, y, w, h);
+        root.order_preserving = self.order_preserving;
+        root.edge_inclusive = self.edge_inclusive;
+        root.loose = self.loose;
+        root.track_order = self.track_order;
+        root.grow = true;
+        root.min_node_size = self.min_node_size;
+        root.next_seq = self.next_seq;
+        root.watches = std::mem::take(&mut self.watches);
+        root.stale = self.stale;
+
+        let old = std::mem::replace(self, root);
+        let q = Quadrant::all()[left as usize + 2 * up as usize];
+        for p in Quadrant::all() {
+            let t = self.new_child(p);
+            self.children.push(t);
+        }
+        self.children[q as usize] = old;
+        true
+    }
+
+    /// Inserts a value keeping its sequence number
+    fn insert_entry(&mut self, v: T, seq: u64) {
+        self.centroid = None;
+
         // If the node is full and not at max-depth
         // OR node already has children
         // we try to insert in a subtree:
-        if !self.is_leaf() || (self.values.len() as u32 >= self.max_values && self.max_depth > 0) {
+        if !self.is_leaf() || (self.values.len() as u32 >= self.max_values && self.can_split()) {
             use Quadrant::*;
             match self.fits(&v) {
                 // If v doesn't fit any quadrant, it will stay in the parent node
-                None => self.values.push(v),
+                None => self.push_value(v, seq),
                 Some(q) => {
                     self.split();
                     match q {
-                        TopLeft => self.children[0].insert(v),
-                        TopRight => self.children[1].insert(v),
-                        BottomLeft => self.children[2].insert(v),
-                        BottomRight => self.children[3].insert(v),
+                        TopLeft => self.children[0].insert_entry(v, seq),
+                        TopRight => self.children[1].insert_entry(v, seq),
+                        BottomLeft => self.children[2].insert_entry(v, seq),
+                        BottomRight => self.children[3].insert_entry(v, seq),
                     }
                 }
             }
         } else {
             // If the actual not is not full or at max-depth:
-            self.values.push(v);
+            self.push_value(v, seq);
+        }
+    }
+
+    /// Re-homes the values of this node at indices `moved`,
+    /// in ascending order, and the values `displaced` from
+    /// its children after they moved
+    ///
+    /// Values still inside this node's zone are inserted
+    /// back from this node, others are pushed in `escaped`
+    /// for the parent to handle, along with their sequence number.
+    fn rehome(
+        &mut self,
+        moved: Vec<usize>,
+        mut displaced: Vec<(T, u64)>,
+        escaped: &mut Vec<(T, u64)>,
+    ) {
+        // Removing from the last index so that
+        // the remaining indices stay valid
+        for i in moved.into_iter().rev() {
+            let bbox = *self.values[i].bounding_box();
+            if !self.belongs(&bbox) {
+                escaped.push(self.remove_at(i));
+            } else if !self.is_leaf() && self.fits(&self.values[i]).is_some() {
+                displaced.push(self.remove_at(i));
+            }
+        }
+
+        for (v, seq) in displaced {
+            if self.belongs(v.bounding_box()) {
+                self.insert_entry(v, seq);
+            } else {
+                escaped.push((v, seq));
+            }
+        }
+    }
+
+    /// Checks if inserting a value with bounding box `bbox`
+    /// would split a node
+    ///
+    /// Follows the path `insert` would take: a split happens if
+    /// the value reaches a full leaf that can still be split and
+    /// fits in one of its quadrants.
+    pub fn insert_would_split(&self, bbox: &AABB<S>) -> bool {
+        if !self.is_leaf() || (self.values.len() as u32 >= self.max_values && self.can_split()) {
+            match self.fits_box(bbox) {
+                None => false,
+                Some(_) if self.is_leaf() => true,
+                Some(q) => self.children[q as usize].insert_would_split(bbox),
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Moves every value into `sink` and makes the tree an
+    /// empty leaf, keeping its zone and configuration
+    ///
+    /// Values are appended to `sink`, allowing callers
+    /// to reuse them. Deleted values are dropped.
+    pub fn clear_into(&mut self, sink: &mut Vec<T>) {
+        let mut entries = Vec::new();
+        self.drain_entries(&mut entries);
+        sink.extend(entries.into_iter().map(|(v, _)| v));
+    }
+
+    /// Moves every value out of the tree, which becomes an
+    /// empty leaf keeping its zone and configuration
+    ///
+    /// Same as `clear_into` with a new `Vec`.
+    pub fn drain(&mut self) -> Vec<T> {
+        let mut values = Vec::new();
+        self.clear_into(&mut values);
+        values
+    }
+
+    /// Drops every value and makes the tree an empty leaf
+    ///
+    /// The zone, configuration, root metadata and watched
+    /// zones are kept, as well as the allocated storage of
+    /// the root.
+    pub fn clear(&mut self) {
+        self.centroid = None;
+        self.stale = false;
+        self.values.clear();
+        self.tombstones.clear();
+        self.order.clear();
+        self.children.clear();
+
+        #[cfg(feature = "diagnostics")]
+        {
+            self.split_record = None;
+        }
+    }
+
+    /// Moves every value with its sequence number into `sink`
+    /// and makes the tree an empty leaf
+    fn drain_entries(&mut self, sink: &mut Vec<(T, u64)>) {
+        self.centroid = None;
+        self.stale = false;
+        self.vacuum_node();
+        sink.extend(self.values.drain(..).zip(self.order.drain(..)));
+        self.tombstones.clear();
+
+        for mut t in std::mem::take(&mut self.children) {
+            t.drain_entries(sink);
+        }
+
+        #[cfg(feature = "diagnostics")]
+        {
+            self.split_record = None;
+        }
+    }
+
+    /// Rebuilds the tree over `new_zone`, keeping its configuration
+    ///
+    /// Values inside `new_zone` are inserted back, others are
+    /// removed from the tree and returned. The metadata of
+    /// every node but the root is lost.
+    pub fn reroot(&mut self, new_zone: AABB<S>) -> Vec<T> {
+        let mut entries = Vec::new();
+        self.drain_entries(&mut entries);
+        self.zone = new_zone;
+
+        let mut dropped = Vec::new();
+        for (v, seq) in entries {
+            if self.holds(v.bounding_box(), self.zone) {
+                self.insert_entry(v, seq);
+            } else {
+                dropped.push(v);
+            }
+        }
+        dropped
+    }
+
+    /// Rebuilds the tree from scratch over the same zone
+    ///
+    /// Every value is taken out and inserted back, so values
+    /// whose bounding box changed through `iter_mut` are found
+    /// again by queries and the nodes fit the current values.
+    /// Unlike `reindex_moved`, every node is rebuilt. The
+    /// metadata of every node but the root is lost.
+    pub fn rebuild(&mut self) {
+        let mut entries = Vec::new();
+        self.drain_entries(&mut entries);
+
+        for (v, seq) in entries {
+            self.insert_root_entry(v, seq);
         }
     }
 
+    /// Whether the node may have children: it is above the
+    /// maximum depth and its quadrants are at least
+    /// `min_node_size` wide and high
+    fn can_split(&self) -> bool {
+        self.max_depth > 0
+            && S::half(self.zone.w) >= self.min_node_size
+            && S::half(self.zone.h) >= self.min_node_size
+    }
+
     /// Split a leaf in four sub trees
     ///
     /// If node is not a leaf nothing happen.
     fn split(&mut self) {
-        if self.children.is_empty() && self.max_depth > 0 {
+        if self.children.is_empty() && self.can_split() {
             // Spawning the children
             for q in Quadrant::all() {
-                self.children.push(QuadTree::<T>::new_child(self, q));
+                self.children.push(QuadTree::<T, M, S>::new_child(self, q));
             }
 
+            // Deleted values are dropped rather than dispatched
+            self.vacuum_node();
+            self.tombstones.clear();
+
             // We dispatch its actual values
             // It is a two step operation to prevent
-            // infinite pop / push behavior
-            let mut vals = LinkedList::<T>::default();
+            // infinite pop / push behavior: values staying
+            // in this node are pushed back after the take
+            let values = std::mem::take(&mut self.values);
+            let order = std::mem::take(&mut self.order);
 
-            while let Some(v) = self.values.pop() {
-                vals.push_back(v);
+            #[cfg(feature = "diagnostics")]
+            let before = values.len();
+
+            for (v, seq) in values.into_iter().zip(order) {
+                self.insert_entry(v, seq);
             }
 
-            while let Some(v) = vals.pop_back() {
-                self.insert(v);
+            #[cfg(feature = "diagnostics")]
+            {
+                self.split_record = Some((before, self.values.len()));
             }
         }
     }
 
-    // fn get_root(&self) -> &QuadTree<T> {
-    //     match self.root {
-    //         None => self,
-    //         Some(r) => r.get_root(),
-    //     }
-    // }
-}
+    /// Returns the values intersecting `area`
+    ///
+    /// Values held by inner nodes, which straddle
+    /// quadrants, are returned along leaf values.
+    pub fn query_range(&self, area: &AABB<S>) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.for_each_in_range(area, &mut |v| res.push(v));
+        res
+    }
 
-impl<T: Collidable> Default for QuadTree<T> {
-    fn default() -> Self {
-        QuadTree::<T>::new(1, 4, 0, 0, 256, 256)
+    /// Returns the values whose bounding box contains `(px, py)`
+    ///
+    /// Only the children whose zone contains the point are
+    /// visited, a point on the edge between sibling zones
+    /// being in both.
+    pub fn query_point(&self, px: S, py: S) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.collect_at_point(px, py, &mut res);
+        res
     }
-}
 
-impl<T: Collidable + Drawable<WinCanvas>> Drawable<WinCanvas> for QuadTree<T> {
-    fn draw(&self, canvas: &mut WinCanvas) -> Result<(), String> {
-        let rect = Rect::from(&self.zone);
+    fn collect_at_point<'a>(&'a self, px: S, py: S, res: &mut Vec<&'a T>) {
+        res.extend(
+            self.live_values()
+                .filter(|v| v.bounding_box().contains_point(px, py)),
+        );
 
-        let mut rng = rand::thread_rng();
-        canvas.set_draw_color(Color::RGB(
-            rng.gen_range(1, 255),
-            rng.gen_range(1, 255),
-            rng.gen_range(1, 255),
-        ));
-        canvas.draw_rect(rect)?;
+        for t in self.children.iter() {
+            if t.bounds().contains_point(px, py) {
+                t.collect_at_point(px, py, res);
+            }
+        }
+    }
 
-        for v in self.values.iter() {
-            v.draw(canvas)?;
+    /// Calls `f` on each value intersecting `area`
+    ///
+    /// Same as `query_range`, without allocating.
+    pub fn for_each_in_range<'a, F: FnMut(&'a T)>(&'a self, area: &AABB<S>, f: &mut F) {
+        for v in self.live_values() {
+            if v.bounding_box().intersects(area) {
+                f(v);
+            }
         }
 
         for t in self.children.iter() {
-            t.draw(canvas)?;
+            if t.bounds().intersects(area) {
+                t.for_each_in_range(area, f);
+            }
         }
+    }
 
-        Ok(())
+    /// Whether a value intersects `region`
+    ///
+    /// Stops at the first match, which makes it cheaper than
+    /// querying the values when only their existence matters,
+    /// e.g. to check that a box can be placed before resolving
+    /// collisions. Values held by inner nodes, which straddle
+    /// quadrants, are checked as well.
+    pub fn any_in_region(&self, region: &AABB<S>) -> bool {
+        self.live_values()
+            .any(|v| v.bounding_box().intersects(region))
+            || self
+                .children
+                .iter()
+                .any(|t| t.bounds().intersects(region) && t.any_in_region(region))
+    }
+
+    /// Whether a proposed box `area` overlaps a value
+    ///
+    /// Same as `any_in_region`.
+    pub fn overlaps_any(&self, area: &AABB<S>) -> bool {
+        self.any_in_region(area)
+    }
+
+    /// Drops the deleted values of this node only
+    fn vacuum_node(&mut self) {
+        if !self.tombstones.contains(&true) {
+            return;
+        }
+
+        let values = std::mem::take(&mut self.values);
+        let order = std::mem::take(&mut self.order);
+        let (values, order) = values
+            .into_iter()
+            .zip(order)
+            .zip(self.tombstones.iter())
+            .filter(|(_, &deleted)| !deleted)
+            .map(|(entry, _)| entry)
+            .unzip();
+        self.values = values;
+        self.order = order;
+        self.tombstones = vec![false; self.values.len()];
+    }
+
+    /// Number of nodes of the tree, including the root
+    pub fn node_count(&self) -> usize {
+        1 + self.children.iter().map(|t| t.node_count()).sum::<usize>()
+    }
+
+    /// Number of splits from the root to the deepest leaf,
+    /// 0 if the root is a leaf
+    pub fn depth(&self) -> u32 {
+        self.children
+            .iter()
+            .map(|t| 1 + t.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether the subtree holds fewer than `n` values
+    ///
+    /// Stops counting at `n`, unlike `len`.
+    fn holds_fewer_than(&self, n: u32) -> bool {
+        self.iter().take(n as usize).count() < n as usize
+    }
+
+    /// Moves every value of the subtree into this node,
+    /// which becomes a leaf
+    ///
+    /// Deleted values are dropped.
+    fn collapse(&mut self) {
+        self.centroid = None;
+        #[cfg(feature = "diagnostics")]
+        {
+            self.split_record = None;
+        }
+
+        for mut t in std::mem::take(&mut self.children) {
+            t.collapse();
+            t.vacuum_node();
+            for (v, seq) in t.values.into_iter().zip(t.order) {
+                self.push_value(v, seq);
+            }
+        }
+    }
+
+    /// Merges the subtrees holding fewer than `max_values`
+    /// values back into their root
+    ///
+    /// `remove` already merges the nodes it empties, this is
+    /// meant after values were dropped in other ways, e.g.
+    /// by `vacuum`. Deleted values are dropped.
+    pub fn prune(&mut self) {
+        for t in self.children.iter_mut() {
+            t.prune();
+        }
+
+        if !self.is_leaf() && self.holds_fewer_than(self.max_values) {
+            self.collapse();
+        }
+    }
+
+    /// Keeps only the values for which `f` returns true
+    ///
+    /// Other values are flagged as deleted like with `mark_deleted`,
+    /// so no value is moved within its node, and the tree is then
+    /// pruned, see `prune`. Deleted values left in the remaining
+    /// nodes are dropped on the next `vacuum`.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: &mut F) {
+        self.retain_node(f);
+        self.prune();
+    }
+
+    fn retain_node<F: FnMut(&T) -> bool>(&mut self, f: &mut F) {
+        self.centroid = None;
+
+        for (v, deleted) in self.values.iter().zip(self.tombstones.iter_mut()) {
+            if !*deleted && !f(v) {
+                *deleted = true;
+            }
+        }
+
+        for t in self.children.iter_mut() {
+            t.retain_node(f);
+        }
+    }
+
+    /// Watches a zone for values entering or leaving it
+    ///
+    /// Events are reported by `update_watched`. A value is
+    /// in the zone when its bounding box intersects it.
+    /// Values moving, inserted with `insert` or `insert_many`,
+    /// or taken out with `remove` are reported, as well as those
+    /// inserted, moved and removed through `QuadTreeWithId`. Values
+    /// dropped by bulk operations, e.g. `clear` or `retain`,
+    /// are not.
+    pub fn watch_zone(&mut self, zone: AABB<S>) -> WatchHandle {
+        self.watches.add(zone)
+    }
+
+    /// Stops watching a zone
+    pub fn unwatch(&mut self, handle: WatchHandle) {
+        self.watches.remove(handle);
     }
 }
 
-impl<T: Collidable + Dynamic> Dynamic for QuadTree<T> {
-    fn update(&mut self, delta: &Duration) -> bool {
-        let mut changed = false;
+/// Operations on trees of `Coord` boxes
+impl<T: Collidable, M: Default> QuadTree<T, M> {
+    /// Creates a builder to configure a new tree
+    ///
+    /// The type of the values must be given, as in
+    /// `QuadTree::<Obj>::builder()`: `QuadTreeBuilder::new()`
+    /// avoids it.
+    pub fn builder() -> QuadTreeBuilder {
+        QuadTreeBuilder::default()
+    }
 
-        for t in &mut self.children {
-            changed = t.update(delta) || changed;
+    /// Inserts several values, in Morton order of
+    /// their bounding box center
+    ///
+    /// Values close in space are inserted one after another,
+    /// which keeps the same nodes in cache. The tree holds the
+    /// same values as if they were inserted one by one, and
+    /// insertion order, if tracked, is the order of `values`.
+    pub fn insert_many<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        let mut entries: Vec<(T, u64)> = values
+            .into_iter()
+            .map(|v| {
+                let seq = self.new_seq();
+                (v, seq)
+            })
+            .collect();
+        entries.sort_by_key(|(v, _)| v.bounding_box().morton_code());
+
+        for (v, seq) in entries {
+            self.watches.inserted(v.bounding_box());
+            self.insert_root_entry(v, seq);
         }
+    }
 
-        let mut moved: LinkedList<usize> = LinkedList::default();
+    /// Replaces every value of the tree by `items`,
+    /// keeping the current partition
+    ///
+    /// Nodes are neither split nor merged: this is cheaper than
+    /// rebuilding the tree when the partition is stable, but
+    /// leaves may end up holding more than `max_values` values
+    /// if the new data is clustered differently.
+    pub fn reset_values(&mut self, items: impl IntoIterator<Item = T>) {
+        self.clear_values();
+        for v in items {
+            let seq = self.new_seq();
+            self.insert_in_partition(v, seq);
+        }
+    }
 
-        for (i, v) in self.values.iter_mut().enumerate() {
-            if v.update(delta) {
-                changed = true;
-                if !v.bounding_box().is_inside(self.zone) {
-                    moved.push_back(i);
+    /// Drops the values of the subtree, keeping its nodes
+    fn clear_values(&mut self) {
+        self.centroid = None;
+        self.values.clear();
+        self.tombstones.clear();
+        self.order.clear();
+        for t in self.children.iter_mut() {
+            t.clear_values();
+        }
+    }
+
+    /// Inserts a value in the existing nodes, without splitting
+    fn insert_in_partition(&mut self, v: T, seq: u64) {
+        self.centroid = None;
+        match self.fits(&v) {
+            Some(q) if !self.is_leaf() => self.children[q as usize].insert_in_partition(v, seq),
+            _ => self.push_value(v, seq),
+        }
+    }
+
+    /// Returns the values intersecting `region` whose bounding
+    /// box is not equal to any of the `exclude` boxes
+    pub fn query_excluding(&self, region: &AABB, exclude: &[AABB]) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.for_each_in_range(region, &mut |v| {
+            if !exclude.contains(v.bounding_box()) {
+                res.push(v);
+            }
+        });
+        res
+    }
+
+    /// Upper bound of the number of values intersecting `region`
+    ///
+    /// Counts the values of every node whose zone intersects
+    /// `region` without testing the values themselves, so the
+    /// result is cheap but may exceed the actual count. Meant
+    /// for pre-sizing buffers with `Vec::with_capacity`.
+    pub fn query_upper_bound(&self, region: &AABB) -> usize {
+        self.live_values().count()
+            + self
+                .children
+                .iter()
+                .filter(|t| t.bounds().intersects(region))
+                .map(|t| t.query_upper_bound(region))
+                .sum::<usize>()
+    }
+
+    /// Returns the values intersecting `region` sorted
+    /// by Morton code of their bounding box center
+    ///
+    /// Processing values in this order improves cache locality.
+    pub fn query_morton(&self, region: &AABB) -> Vec<&T> {
+        let mut res = self.query_range(region);
+        res.sort_by_key(|v| v.bounding_box().morton_code());
+        res
+    }
+
+    /// Returns the values intersecting the area covered by
+    /// `start` while it is translated by `(dx, dy)`
+    ///
+    /// The area is approximated by the bounding box of `start`
+    /// and its translation, so values near a diagonal motion
+    /// may be returned without being touched.
+    pub fn query_swept(&self, start: &AABB, dx: Coord, dy: Coord) -> Vec<&T> {
+        let mut end = *start;
+        end.translate(dx, dy);
+        self.query_range(&start.union(&end))
+    }
+
+    /// Returns the values whose bounding box is crossed
+    /// by the segment from `(x0, y0)` to `(x1, y1)`
+    ///
+    /// Subtrees the segment misses are skipped.
+    pub fn query_segment(&self, x0: Coord, y0: Coord, x1: Coord, y1: Coord) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.collect_on_segment((x0, y0, x1, y1), &mut res);
+        res
+    }
+
+    fn collect_on_segment<'a>(&'a self, seg: (Coord, Coord, Coord, Coord), res: &mut Vec<&'a T>) {
+        let (x0, y0, x1, y1) = seg;
+        res.extend(
+            self.live_values()
+                .filter(|v| v.bounding_box().intersects_segment(x0, y0, x1, y1)),
+        );
+
+        for t in self.children.iter() {
+            if t.bounds().intersects_segment(x0, y0, x1, y1) {
+                t.collect_on_segment(seg, res);
+            }
+        }
+    }
+
+    /// Returns the first value touched by `start` while it is
+    /// translated by `(dx, dy)`
+    ///
+    /// A value already touching `start` is hit immediately.
+    pub fn first_hit_moving(&self, start: &AABB, dx: Coord, dy: Coord) -> Option<&T> {
+        let (ox, oy) = (start.x as f64, start.y as f64);
+        let (fdx, fdy) = (dx as f64, dy as f64);
+        self.query_swept(start, dx, dy)
+            .into_iter()
+            .filter_map(|v| {
+                // `start` touches the value when its top-left corner
+                // is in the value's box grown by the size of `start`
+                let b = v.bounding_box();
+                let grown = AABB {
+                    x: b.x - start.w as Coord,
+                    y: b.y - start.h as Coord,
+                    w: b.w + start.w,
+                    h: b.h + start.h,
+                };
+                let t = grown.ray_entry(ox, oy, fdx, fdy)?;
+                if t <= 1. {
+                    Some((t, v))
+                } else {
+                    None
                 }
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, v)| v)
+    }
+
+    /// Smallest distance between `bbox` and the bounding box
+    /// of a value, values whose box is equal to `bbox` being
+    /// ignored
+    ///
+    /// Returns `None` if there is no other value.
+    pub fn min_distance_from(&self, bbox: &AABB) -> Option<f64> {
+        let mut best = None;
+        self.closest_distance(bbox, &mut best);
+        best
+    }
+
+    fn closest_distance(&self, bbox: &AABB, best: &mut Option<f64>) {
+        for v in self.live_values() {
+            let other = v.bounding_box();
+            if other == bbox {
+                continue;
+            }
+            let d = other.distance_to(bbox);
+            if best.is_none_or(|b| d < b) {
+                *best = Some(d);
+            }
+        }
+
+        for t in self.children.iter() {
+            // Values of a subtree can't be closer than its zone
+            if best.is_none_or(|b| t.bounds().distance_to(bbox) < b) {
+                t.closest_distance(bbox, best);
+            }
+        }
+    }
+
+    /// Returns the zones of the leaves intersecting `bbox`
+    ///
+    /// A box straddling several cells is attributed to each of them.
+    pub fn coverage_of(&self, bbox: &AABB) -> Vec<AABB> {
+        let mut res = Vec::new();
+        self.collect_coverage(bbox, &mut res);
+        res
+    }
+
+    fn collect_coverage(&self, bbox: &AABB, res: &mut Vec<AABB>) {
+        if !self.zone.intersects(bbox) {
+            return;
+        }
+
+        if self.is_leaf() {
+            res.push(self.zone);
+        }
+        for t in self.children.iter() {
+            t.collect_coverage(bbox, res);
+        }
+    }
+
+    /// Counts the values whose bounding box intersects the
+    /// circle of center `(cx, cy)` and radius `radius`
+    ///
+    /// Subtrees whose zone is out of the circle are skipped.
+    pub fn count_in_radius(&self, cx: Coord, cy: Coord, radius: Size) -> usize {
+        let center = AABB::from((cx, cy, 0, 0));
+        let radius = radius as f64;
+        self.count_within(&center, radius)
+    }
+
+    fn count_within(&self, center: &AABB, radius: f64) -> usize {
+        let own = self
+            .live_values()
+            .filter(|v| v.bounding_box().distance_to(center) <= radius)
+            .count();
+
+        own + self
+            .children
+            .iter()
+            .filter(|t| t.bounds().distance_to(center) <= radius)
+            .map(|t| t.count_within(center, radius))
+            .sum::<usize>()
+    }
+
+    /// Returns the value closest to `(px, py)`
+    ///
+    /// Distances are measured to the bounding boxes, 0 if the
+    /// point is inside. See `nearest_matching` for the search.
+    pub fn nearest(&self, px: Coord, py: Coord) -> Option<&T> {
+        self.nearest_matching(px, py, |_| true)
+    }
+
+    /// Returns the value closest to `(px, py)` among those
+    /// for which `pred` returns `true`
+    ///
+    /// Distances are measured to the bounding boxes. Children are
+    /// visited closest first, and subtrees whose zone is farther
+    /// than the best match found so far are skipped.
+    pub fn nearest_matching<P: Fn(&T) -> bool>(&self, px: Coord, py: Coord, pred: P) -> Option<&T> {
+        let mut best = None;
+        self.nearest_within(px, py, &pred, &mut best);
+        best.map(|(_, v)| v)
+    }
+
+    /// Squared distances are compared as integers, so that
+    /// ties and far away points are exact
+    fn nearest_within<'a, P: Fn(&T) -> bool>(
+        &'a self,
+        px: Coord,
+        py: Coord,
+        pred: &P,
+        best: &mut Option<(u64, &'a T)>,
+    ) {
+        for v in self.live_values() {
+            let d = v.bounding_box().distance_sq_to_point(px, py);
+            if best.is_none_or(|(b, _)| d < b) && pred(v) {
+                *best = Some((d, v));
+            }
+        }
+
+        // Children are visited by distance to their centroid when
+        // cached, which better predicts where the closest value is
+        let (fx, fy) = (px as f64, py as f64);
+        let mut children: Vec<(f64, &QuadTree<T, M>)> = self
+            .children
+            .iter()
+            .map(|t| match t.centroid {
+                Some((cx, cy)) => ((cx - fx).hypot(cy - fy), t),
+                None => ((t.bounds().distance_sq_to_point(px, py) as f64).sqrt(), t),
+            })
+            .collect();
+        children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        for (_, t) in children {
+            if best.is_none_or(|(b, _)| t.bounds().distance_sq_to_point(px, py) < b) {
+                t.nearest_within(px, py, pred, best);
             }
         }
+    }
 
-        for i in moved {
-            let v = self.values.remove(i);
-            //self.get_root().insert(v);
+    /// Caches the average center of the values of every subtree
+    ///
+    /// Nearest neighbor searches then visit children by distance
+    /// to their centroid, reaching good candidates sooner and
+    /// pruning more. The cache of a node is invalidated when a
+    /// value of its subtree is inserted, removed or moved.
+    pub fn build_centroid_cache(&mut self) {
+        self.sum_centers();
+    }
+
+    /// Caches the centroid of the subtree and returns
+    /// the sum of its value centers and their number
+    fn sum_centers(&mut self) -> (f64, f64, usize) {
+        let (mut sx, mut sy, mut n) = (0., 0., 0);
+        for v in self.live_values() {
+            let b = v.bounding_box();
+            sx += b.x as f64 + b.w as f64 / 2.;
+            sy += b.y as f64 + b.h as f64 / 2.;
+            n += 1;
+        }
+        for t in self.children.iter_mut() {
+            let (cx, cy, cn) = t.sum_centers();
+            sx += cx;
+            sy += cy;
+            n += cn;
         }
 
-        return changed;
+        self.centroid = if n > 0 {
+            Some((sx / n as f64, sy / n as f64))
+        } else {
+            None
+        };
+        (sx, sy, n)
+    }
+
+    /// Collects every value of the tree
+    fn collect_values<'a>(&'a self, res: &mut Vec<&'a T>) {
+        res.extend(self.live_values());
+        for t in self.children.iter() {
+            t.collect_values(res);
+        }
+    }
+
+    /// Returns every value with the depth of the node
+    /// storing it, the root being at depth 0
+    pub fn iter_with_depth(&self) -> Vec<(&T, u32)> {
+        let mut res = Vec::new();
+        self.collect_with_depth(0, &mut res);
+        res
+    }
+
+    fn collect_with_depth<'a>(&'a self, depth: u32, res: &mut Vec<(&'a T, u32)>) {
+        res.extend(self.live_values().map(|v| (v, depth)));
+        for t in self.children.iter() {
+            t.collect_with_depth(depth + 1, res);
+        }
+    }
+
+    /// Returns every value in insertion order
+    ///
+    /// Insertion order is only tracked by trees built with
+    /// `QuadTreeBuilder::track_order`, values of other trees
+    /// are returned in no particular order. Values keep their
+    /// rank when they move or when nodes split.
+    pub fn iter_insertion_order(&self) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.collect_entries(&mut res);
+        res.sort_by_key(|&(_, seq)| seq);
+        res.into_iter().map(|(v, _)| v).collect()
+    }
+
+    fn collect_entries<'a>(&'a self, res: &mut Vec<(&'a T, u64)>) {
+        for (i, v) in self.values.iter().enumerate() {
+            if !self.tombstones[i] {
+                res.push((v, self.order[i]));
+            }
+        }
+        for t in self.children.iter() {
+            t.collect_entries(res);
+        }
+    }
+
+    /// Returns every value sorted by distance between
+    /// `(px, py)` and the center of their bounding box
+    pub fn sorted_by_distance(&self, px: Coord, py: Coord) -> Vec<&T> {
+        let mut res = Vec::new();
+        self.collect_values(&mut res);
+        res.sort_by_key(|v| {
+            let b = v.bounding_box();
+            let dx = b.x.widen() + (b.w / 2).widen() as i64 - px.widen();
+            let dy = b.y.widen() + (b.h / 2).widen() as i64 - py.widen();
+            dx * dx + dy * dy
+        });
+        res
+    }
+
+    /// Counts the values by area of their bounding box
+    ///
+    /// `buckets` are ascending area boundaries: the count at
+    /// index `i` is that of the values whose area is at most
+    /// `buckets[i]` and above `buckets[i - 1]`. An extra last
+    /// count holds the values larger than every boundary.
+    pub fn size_histogram(&self, buckets: &[u64]) -> Vec<usize> {
+        let mut res = vec![0; buckets.len() + 1];
+        for v in self.iter() {
+            let area = v.bounding_box().area();
+            res[buckets.partition_point(|&b| b < area)] += 1;
+        }
+        res
+    }
+
+    /// Greatest number of value boxes overlapping at a single point
+    ///
+    /// The maximum is reached on the left edge of one of the boxes:
+    /// for each left edge, the boxes crossing that vertical line
+    /// are swept along y, counting how many y-intervals overlap.
+    /// Edges are inclusive, as for `AABB::intersects`. This costs
+    /// `O(n² log n)` for `n` values, meant for offline analysis.
+    pub fn max_overlap_depth(&self) -> usize {
+        let mut values = Vec::new();
+        self.collect_values(&mut values);
+        let boxes: Vec<(i64, i64, i64, i64)> = values
+            .iter()
+            .map(|v| {
+                let b = v.bounding_box();
+                let (x, y) = (b.x.widen(), b.y.widen());
+                (x, x + b.w.widen() as i64, y, y + b.h.widen() as i64)
+            })
+            .collect();
+
+        let mut best = 0;
+        let mut events = Vec::new();
+        for &(line, _, _, _) in boxes.iter() {
+            events.clear();
+            for &(x0, x1, y0, y1) in boxes.iter() {
+                if x0 <= line && line <= x1 {
+                    // Openings sort before closings at the same y
+                    events.push((y0, 0));
+                    events.push((y1, 1));
+                }
+            }
+            events.sort();
+
+            let mut depth = 0;
+            for &(_, closing) in events.iter() {
+                if closing == 0 {
+                    depth += 1;
+                    best = best.max(depth);
+                } else {
+                    depth -= 1;
+                }
+            }
+        }
+        best
+    }
+
+    /// Merges the value boxes which transitively overlap or touch
+    /// into their common bounding box
+    ///
+    /// The result is a coarser over-approximation of the values:
+    /// a merged box also covers the space between its members.
+    /// This costs `O(n²)` for `n` values.
+    pub fn merged_boxes(&self) -> Vec<AABB> {
+        let mut values = Vec::new();
+        self.collect_values(&mut values);
+        let boxes: Vec<AABB> = values.iter().map(|v| *v.bounding_box()).collect();
+
+        fn find(group: &mut [usize], mut i: usize) -> usize {
+            while group[i] != i {
+                group[i] = group[group[i]];
+                i = group[i];
+            }
+            i
+        }
+
+        let mut group: Vec<usize> = (0..boxes.len()).collect();
+        for i in 0..boxes.len() {
+            for j in i + 1..boxes.len() {
+                if boxes[i].intersects(&boxes[j]) {
+                    let (a, b) = (find(&mut group, i), find(&mut group, j));
+                    group[b] = a;
+                }
+            }
+        }
+
+        let mut merged: Vec<Option<AABB>> = vec![None; boxes.len()];
+        for (i, b) in boxes.iter().enumerate() {
+            let g = find(&mut group, i);
+            merged[g] = Some(match merged[g] {
+                Some(m) => m.union(b),
+                None => *b,
+            });
+        }
+        merged.into_iter().flatten().collect()
+    }
+
+    /// Sets whether removals keep the order of the remaining values
+    ///
+    /// Values are stored in a `Vec` per node: keeping their order
+    /// shifts every following value on removal, while not keeping
+    /// it swaps the last value into the freed slot in constant time.
+    /// Order is preserved by default.
+    pub fn order_preserving(&mut self, flag: bool) {
+        self.order_preserving = flag;
+        for t in self.children.iter_mut() {
+            t.order_preserving(flag);
+        }
+    }
+
+    /// Removes and returns the value with sequence number `seq`,
+    /// placed in the tree while its bounding box was `bbox`
+    ///
+    /// The box is used as a search hint: only the nodes on the
+    /// path `insert` would have taken are visited.
+    fn take(&mut self, bbox: &AABB, seq: u64) -> Option<(T, u64)> {
+        self.centroid = None;
+        if let Some(i) = self.position_of_entry(seq) {
+            return Some(self.remove_at(i));
+        }
+
+        match self.fits_box(bbox) {
+            Some(q) if !self.is_leaf() => self.children[q as usize].take(bbox, seq),
+            _ => None,
+        }
+    }
+
+    /// Returns the value with sequence number `seq`,
+    /// placed while its bounding box was `bbox`, see `take`
+    fn get_entry(&self, bbox: &AABB, seq: u64) -> Option<&T> {
+        if let Some(i) = self.position_of_entry(seq) {
+            return Some(&self.values[i]);
+        }
+
+        match self.fits_box(bbox) {
+            Some(q) if !self.is_leaf() => self.children[q as usize].get_entry(bbox, seq),
+            _ => None,
+        }
+    }
+
+    fn get_entry_mut(&mut self, bbox: &AABB, seq: u64) -> Option<&mut T> {
+        if let Some(i) = self.position_of_entry(seq) {
+            return Some(&mut self.values[i]);
+        }
+
+        match self.fits_box(bbox) {
+            Some(q) if !self.is_leaf() => self.children[q as usize].get_entry_mut(bbox, seq),
+            _ => None,
+        }
+    }
+
+    /// Index in the node of the live value
+    /// with sequence number `seq`
+    fn position_of_entry(&self, seq: u64) -> Option<usize> {
+        (0..self.values.len()).find(|&i| !self.tombstones[i] && self.order[i] == seq)
+    }
+
+    /// Index in the node of the first live value
+    /// whose bounding box is equal to `bbox`
+    fn position_of(&self, bbox: &AABB) -> Option<usize> {
+        self.values
+            .iter()
+            .zip(self.tombstones.iter())
+            .position(|(v, &deleted)| !deleted && v.bounding_box() == bbox)
+    }
+
+    /// Zone of the node storing the first value whose
+    /// bounding box is equal to `bbox`
+    ///
+    /// Meant to highlight the cell of a selected object.
+    /// Returns `None` if no such value was found.
+    pub fn drawing_cell(&self, bbox: &AABB) -> Option<AABB> {
+        if self.position_of(bbox).is_some() {
+            return Some(self.zone);
+        }
+
+        if self.is_leaf() {
+            return None;
+        }
+
+        match self.fits_box(bbox) {
+            Some(q) => self.children[q as usize].drawing_cell(bbox),
+            None => None,
+        }
+    }
+
+    /// Logically deletes the first value whose bounding box
+    /// is equal to `bbox`
+    ///
+    /// The value is only flagged as deleted: it is ignored by
+    /// every query but stays in its node, so deletion does
+    /// not move any other value. It is dropped on the next
+    /// `vacuum` or when its node splits.
+    /// Returns `false` if no such value was found.
+    pub fn mark_deleted(&mut self, bbox: &AABB) -> bool {
+        self.centroid = None;
+        if let Some(i) = self.position_of(bbox) {
+            self.tombstones[i] = true;
+            return true;
+        }
+
+        if self.is_leaf() {
+            return false;
+        }
+
+        match self.fits_box(bbox) {
+            Some(q) => self.children[q as usize].mark_deleted(bbox),
+            None => false,
+        }
+    }
+
+    /// Applies `f` to the first value whose bounding box is
+    /// equal to `locate`, re-homing it if `f` returns `true`
+    /// to signal that it moved
+    ///
+    /// Unlike `Dynamic::update`, only this value is visited,
+    /// along with the nodes on its path.
+    /// Returns `false` if no such value was found.
+    pub fn update_one<F: FnOnce(&mut T) -> bool>(&mut self, locate: &AABB, f: F) -> bool {
+        let mut escaped = Vec::new();
+        let found = self.update_located(locate, f, &mut escaped);
+
+        // Values out of the whole tree are kept by the root
+        for (v, seq) in escaped {
+            self.insert_root_entry(v, seq);
+        }
+        found
+    }
+
+    fn update_located<F: FnOnce(&mut T) -> bool>(
+        &mut self,
+        locate: &AABB,
+        f: F,
+        escaped: &mut Vec<(T, u64)>,
+    ) -> bool {
+        self.centroid = None;
+        if let Some(i) = self.position_of(locate) {
+            if f(&mut self.values[i]) {
+                self.rehome(vec![i], Vec::new(), escaped);
+            }
+            return true;
+        }
+
+        if self.is_leaf() {
+            return false;
+        }
+
+        match self.fits_box(locate) {
+            Some(q) => {
+                let mut displaced = Vec::new();
+                let found = self.children[q as usize].update_located(locate, f, &mut displaced);
+                self.rehome(Vec::new(), displaced, escaped);
+                found
+            }
+            None => false,
+        }
+    }
+
+    /// Re-homes the values moved by `step` or through `iter_mut`
+    ///
+    /// Only the nodes holding moved values are visited, and
+    /// values still fitting their node are left in place.
+    pub fn reindex_moved(&mut self) {
+        let mut escaped = Vec::new();
+        self.reindex_node(&mut escaped);
+
+        // Values out of the whole tree are kept by the root
+        for (v, seq) in escaped {
+            self.insert_root_entry(v, seq);
+        }
+    }
+
+    fn reindex_node(&mut self, escaped: &mut Vec<(T, u64)>) {
+        if !self.stale {
+            return;
+        }
+        self.stale = false;
+        self.centroid = None;
+
+        let mut displaced = Vec::new();
+        for t in &mut self.children {
+            t.reindex_node(&mut displaced);
+        }
+
+        let moved = (0..self.values.len())
+            .filter(|&i| !self.tombstones[i])
+            .collect();
+        self.rehome(moved, displaced, escaped);
+    }
+
+    /// Drops every value flagged by `mark_deleted`
+    pub fn vacuum(&mut self) {
+        self.vacuum_node();
+        for t in self.children.iter_mut() {
+            t.vacuum();
+        }
+    }
+
+    /// Lazily iterates over the pairs of values whose
+    /// bounding boxes intersect
+    ///
+    /// Each pair is yielded once and a value is never paired
+    /// with itself. Pairs are computed as the iterator is
+    /// consumed, so stopping early skips the remaining work.
+    pub fn iter_overlapping_pairs(&self) -> impl Iterator<Item = (&T, &T)> {
+        OverlappingPairs::new(self)
+    }
+
+    /// Returns every pair of values whose bounding boxes intersect
+    ///
+    /// Values are only checked against the values of the
+    /// subtrees they may intersect, see `iter_overlapping_pairs`.
+    pub fn collisions(&self) -> Vec<(&T, &T)> {
+        self.iter_overlapping_pairs().collect()
+    }
+
+    /// Lazily iterates over the values hit by the ray starting
+    /// at `(ox, oy)` with direction `(dx, dy)`
+    ///
+    /// Values are yielded by distance at which the ray enters
+    /// their bounding box. The ordering is best-effort: it relies
+    /// on values being inside the zone of their node, which
+    /// may not hold for values moved since their insertion.
+    pub fn raycast_iter(&self, ox: f64, oy: f64, dx: f64, dy: f64) -> impl Iterator<Item = &T> {
+        Raycast::new(self, ox, oy, dx, dy)
+    }
+
+    /// Share of the leaves holding at least one value
+    ///
+    /// A low ratio suggests the tree is over-subdivided.
+    pub fn occupancy_ratio(&self) -> f64 {
+        let (occupied, leaves) = self.leaf_occupancy();
+        occupied as f64 / leaves as f64
+    }
+
+    /// Counts the non-empty leaves and the leaves of the tree
+    fn leaf_occupancy(&self) -> (usize, usize) {
+        if self.is_leaf() {
+            return (self.live_values().next().is_some() as usize, 1);
+        }
+
+        self.children
+            .iter()
+            .map(|t| t.leaf_occupancy())
+            .fold((0, 0), |(o, l), (co, cl)| (o + co, l + cl))
+    }
+
+    /// Exports the structure of the tree in the Graphviz DOT format
+    ///
+    /// Each node is pinned at the center of its zone, so that
+    /// rendering with `neato -n` draws the nodes at their actual
+    /// spatial coordinates. Graphviz's y axis points up: the
+    /// drawing is mirrored vertically compared to the screen.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph quadtree {\n    node [shape=box];\n");
+        self.write_dot(&mut dot, &mut 0);
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the DOT statements of the subtree, numbering
+    /// nodes from `next_id`, and returns the id of this node
+    fn write_dot(&self, dot: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let z = &self.zone;
+        writeln!(
+            dot,
+            "    n{} [label=\"{},{} {}x{}\\n{} values\", pos=\"{}\"];",
+            id,
+            z.x,
+            z.y,
+            z.w,
+            z.h,
+            self.live_values().count(),
+            z.graphviz_pos()
+        )
+        .unwrap();
+
+        for t in self.children.iter() {
+            let child = t.write_dot(dot, next_id);
+            writeln!(dot, "    n{} -- n{};", id, child).unwrap();
+        }
+        id
+    }
+
+    /// Collapses subtrees until the tree has at most `max_nodes` nodes
+    ///
+    /// At each step the group of four sibling leaves holding
+    /// the fewest values is merged back into its parent.
+    /// Merged nodes may end up holding more than `max_values`
+    /// values, and at least the root is always kept.
+    pub fn limit_nodes(&mut self, max_nodes: usize) {
+        while self.node_count() > max_nodes {
+            let path = match self.smallest_leaf_group() {
+                Some((_, path)) => path,
+                None => return,
+            };
+
+            let mut node = &mut *self;
+            for i in path.into_iter().rev() {
+                node = &mut node.children[i];
+            }
+            node.collapse();
+        }
+    }
+
+    /// Finds the node whose children are all leaves holding
+    /// the fewest values
+    ///
+    /// Returns the number of values in the children and the
+    /// path of child indices to the node, from the node up.
+    fn smallest_leaf_group(&self) -> Option<(usize, Vec<usize>)> {
+        if self.is_leaf() {
+            return None;
+        }
+
+        if self.children.iter().all(|t| t.is_leaf()) {
+            let count = self.children.iter().map(|t| t.live_values().count()).sum();
+            return Some((count, Vec::new()));
+        }
+
+        self.children
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| {
+                let (count, mut path) = t.smallest_leaf_group()?;
+                path.push(i);
+                Some((count, path))
+            })
+            .min_by_key(|(count, _)| *count)
+    }
+
+    /// Iterates over the values of the subtree of one
+    /// of the root's quadrants, `None` if the root is a leaf
+    ///
+    /// Values held by the root itself are not part of any
+    /// quadrant's subtree. The four iterators are independent
+    /// and can be consumed in parallel.
+    pub fn iter_quadrant(&self, q: Quadrant) -> Option<impl Iterator<Item = &T>> {
+        self.children.get(q as usize).map(Iter::new)
+    }
+
+    /// Returns the values of one of the root's quadrants
+    ///
+    /// These are the values of the quadrant's subtree, plus the
+    /// values held by the root whose bounding box intersects the
+    /// quadrant: a value straddling quadrants is returned for
+    /// each of them.
+    pub fn values_in_quadrant(&self, q: Quadrant) -> Vec<&T> {
+        let zone = Quadrant::quadrant_bbox(&self.zone, q);
+        let mut res: Vec<&T> = self
+            .live_values()
+            .filter(|v| v.bounding_box().intersects(&zone))
+            .collect();
+
+        if let Some(t) = self.children.get(q as usize) {
+            res.extend(t.iter());
+        }
+        res
+    }
+
+    /// Returns the deepest node whose zone contains both boxes
+    ///
+    /// The root is returned if no child contains both,
+    /// even if the boxes are not inside the root's zone.
+    pub fn common_ancestor(&self, a: &AABB, b: &AABB) -> &QuadTree<T, M> {
+        let mut node = self;
+        while let Some(t) = node
+            .children
+            .iter()
+            .find(|t| a.is_inside(t.zone) && b.is_inside(t.zone))
+        {
+            node = t;
+        }
+        node
+    }
+
+    /// Returns the zone and number of values of every node
+    /// holding more than `max_values` values
+    ///
+    /// This happens with values straddling quadrants, which
+    /// stay in their parent, and with leaves at `max_depth`.
+    /// Such nodes are hotspots slowing queries down.
+    pub fn overfull_nodes(&self) -> Vec<(AABB, usize)> {
+        let mut res = Vec::new();
+        self.collect_overfull(&mut res);
+        res
+    }
+
+    fn collect_overfull(&self, res: &mut Vec<(AABB, usize)>) {
+        let count = self.live_values().count();
+        if count > self.max_values as usize {
+            res.push((self.zone, count));
+        }
+        for t in self.children.iter() {
+            t.collect_overfull(res);
+        }
+    }
+
+    /// Returns the zone of the node reached by following `path`
+    /// from this node, `None` if the path goes past a leaf
+    pub fn zone_of_path(&self, path: &[Quadrant]) -> Option<AABB> {
+        let mut node = self;
+        for &q in path {
+            node = node.children.get(q as usize)?;
+        }
+        Some(node.zone)
+    }
+
+    /// Returns the metadata of the deepest node containing
+    /// the point `(px, py)`, `None` if the point is outside the tree
+    pub fn node_meta(&self, px: Coord, py: Coord) -> Option<&M> {
+        let p = AABB::from((px, py, 0, 0));
+        if !p.is_inside(self.zone) {
+            return None;
+        }
+
+        let mut node = self;
+        while let Some(t) = node.children.iter().find(|t| p.is_inside(t.zone)) {
+            node = t;
+        }
+        Some(&node.meta)
+    }
+
+    /// Returns a mutable reference to the metadata of the deepest
+    /// node containing the point `(px, py)`, `None` if the point
+    /// is outside the tree
+    ///
+    /// Metadata is attached to nodes, not to regions: nodes
+    /// created by a later split start with `M::default()`.
+    pub fn node_meta_mut(&mut self, px: Coord, py: Coord) -> Option<&mut M> {
+        let p = AABB::from((px, py, 0, 0));
+        if !p.is_inside(self.zone) {
+            return None;
+        }
+
+        let mut node = self;
+        while let Some(i) = node.children.iter().position(|t| p.is_inside(t.zone)) {
+            node = &mut node.children[i];
+        }
+        Some(&mut node.meta)
+    }
+
+    /// Wraps the tree to keep track of the values inserted
+    /// through the wrapper using `ValueId` identifiers.
+    ///
+    /// Values already present in the tree are kept but
+    /// are not given an identifier.
+    pub fn with_id_tracking(self) -> QuadTreeWithId<T, M> {
+        QuadTreeWithId::new(self)
+    }
+}
+
+impl<T: CollidableMut, M: Default> QuadTree<T, M> {
+    /// Replaces the bounding box of every value by its image
+    /// through `f`, then re-homes the values which no longer
+    /// fit their node
+    pub fn transform_boxes<F: Fn(&AABB) -> AABB>(&mut self, f: F) {
+        let mut escaped = Vec::new();
+        self.transform_node(&f, &mut escaped);
+
+        // Values out of the whole tree are kept by the root
+        for (v, seq) in escaped {
+            self.insert_root_entry(v, seq);
+        }
+    }
+
+    fn transform_node<F: Fn(&AABB) -> AABB>(&mut self, f: &F, escaped: &mut Vec<(T, u64)>) {
+        self.centroid = None;
+        let mut displaced = Vec::new();
+        for t in &mut self.children {
+            t.transform_node(f, &mut displaced);
+        }
+
+        // Deleted values must not be re-homed as live ones
+        self.vacuum_node();
+        for v in self.values.iter_mut() {
+            let bbox = f(v.bounding_box());
+            *v.bounding_box_mut() = bbox;
+        }
+
+        let moved = (0..self.values.len()).collect();
+        self.rehome(moved, displaced, escaped);
+    }
+}
+
+impl<S: Scalar, T: Collidable<S> + PartialEq, M: Default> QuadTree<T, M, S> {
+    /// Removes and returns a value equal to `v`
+    ///
+    /// Only the nodes on the path `insert` would have taken
+    /// for `v` are visited. Subtrees left holding fewer than
+    /// `max_values` values are merged back into their root.
+    ///
+    /// Since it is returned, the value is taken out of its node
+    /// rather than flagged as deleted, which moves the following
+    /// values, see `order_preserving`. `mark_deleted` deletes a
+    /// value without moving any other.
+    pub fn remove(&mut self, v: &T) -> Option<T> {
+        self.centroid = None;
+        let found = self
+            .values
+            .iter()
+            .zip(self.tombstones.iter())
+            .position(|(w, &deleted)| !deleted && w == v);
+
+        let res = match found {
+            Some(i) => Some(self.remove_at(i).0),
+            None if self.is_leaf() => None,
+            None => match self.fits(v) {
+                Some(q) => self.children[q as usize].remove(v),
+                None => None,
+            },
+        };
+
+        if let Some(v) = &res {
+            self.watches.removed(v.bounding_box());
+        }
+        if res.is_some() && !self.is_leaf() && self.holds_fewer_than(self.max_values) {
+            self.collapse();
+        }
+        res
+    }
+
+    /// Whether the tree holds a value equal to `v`
+    ///
+    /// Only the nodes on the path `v` would be inserted
+    /// along are visited, as for `remove`.
+    pub fn contains(&self, v: &T) -> bool {
+        if self.live_values().any(|w| w == v) {
+            return true;
+        }
+
+        match self.fits(v) {
+            Some(q) if !self.is_leaf() => self.children[q as usize].contains(v),
+            _ => false,
+        }
+    }
+}
+
+impl<T: Collidable + PartialEq, M: Default> QuadTree<T, M> {
+    /// Interpolates the bounding boxes of the values of two trees
+    ///
+    /// Values of `a` are matched with the values of `b` they are
+    /// equal to, so `PartialEq` should compare identities (e.g.
+    /// an entity id) rather than positions. For each matched
+    /// value the result holds its box in `a` and its box
+    /// interpolated towards `b` by `t`, in `[0, 1]`.
+    /// Values present in only one tree are ignored.
+    ///
+    /// `T` being only `PartialEq`, each value of `a` is searched
+    /// among every value of `b`: this costs `O(n·m)` for `n` and
+    /// `m` values, which is fine for a frame's worth of moving
+    /// objects but not for large trees.
+    pub fn spatial_interpolate(
+        a: &QuadTree<T, M>,
+        b: &QuadTree<T, M>,
+        t: f32,
+    ) -> Vec<(AABB, AABB)> {
+        let mut olds = Vec::new();
+        let mut news = Vec::new();
+        a.collect_values(&mut olds);
+        b.collect_values(&mut news);
+
+        olds.into_iter()
+            .filter_map(|old| {
+                let new = news.iter().find(|new| **new == old)?;
+                let bbox = old.bounding_box();
+                Some((*bbox, bbox.lerp(new.bounding_box(), t)))
+            })
+            .collect()
+    }
+}
+
+impl<'a, U: Collidable, M: Default> QuadTree<&'a U, M> {
+    /// Indexes the values of `items` without moving them
+    ///
+    /// The zone of the tree is the smallest box holding every
+    /// item, other options keep the values of `QuadTree::default()`.
+    pub fn from_refs(items: &'a [U]) -> QuadTree<&'a U, M> {
+        let mut builder = QuadTree::<&U, M>::builder();
+        if let Some(zone) = items
+            .iter()
+            .map(|v| *v.bounding_box())
+            .reduce(|a, b| a.union(&b))
+        {
+            builder = builder.zone(zone);
+        }
+
+        let mut qt = builder.build();
+        qt.insert_many(items);
+        qt
+    }
+}
+
+impl<T: Collidable + Clone, M: Default> QuadTree<T, M> {
+    /// Creates a tree with the same zone and configuration
+    /// holding clones of the values intersecting `region`
+    ///
+    /// The new tree is independent of this one, node
+    /// metadata and watched zones are not copied.
+    pub fn clone_region(&self, region: &AABB) -> QuadTree<T, M> {
+        let mut qt = self.empty_like();
+        for v in self.query_range(region) {
+            qt.insert(v.clone());
+        }
+        qt
+    }
+}
+
+impl<S: Scalar, T: Collidable<S>, M> QuadTree<T, M, S> {
+    /// Zone covered by the tree
+    pub fn zone(&self) -> &AABB<S> {
+        &self.zone
+    }
+
+    /// Number of values a leaf holds before splitting
+    pub fn max_values(&self) -> u32 {
+        self.max_values
+    }
+
+    /// Maximum number of splits from this node to a leaf
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Zone holding every value of the subtree, but
+    /// possibly values kept by the root
+    ///
+    /// This is the zone of the node, grown by `loose_bounds`
+    /// in loose trees.
+    fn bounds(&self) -> AABB<S> {
+        if self.loose {
+            loose_bounds(&self.zone)
+        } else {
+            self.zone
+        }
+    }
+
+    /// Iterates over the values of this node
+    /// which are not logically deleted
+    fn live_values(&self) -> impl Iterator<Item = &T> {
+        self.values
+            .iter()
+            .zip(self.tombstones.iter())
+            .filter(|(_, &deleted)| !deleted)
+            .map(|(v, _)| v)
+    }
+
+    /// Stores a value in this node with its sequence number
+    fn push_value(&mut self, v: T, seq: u64) {
+        self.values.push(v);
+        self.tombstones.push(false);
+        self.order.push(seq);
+    }
+
+    /// Removes the value at index `i` of the node and returns
+    /// it with its sequence number, keeping the values order
+    /// or not according to `order_preserving`
+    fn remove_at(&mut self, i: usize) -> (T, u64) {
+        if self.order_preserving {
+            self.tombstones.remove(i);
+            (self.values.remove(i), self.order.remove(i))
+        } else {
+            self.tombstones.swap_remove(i);
+            (self.values.swap_remove(i), self.order.swap_remove(i))
+        }
+    }
+}
+
+impl<T: Collidable, M> QuadTree<T, M> {
+    /// Outlines the zone of every node of the tree in `color`,
+    /// without drawing the values
+    pub fn draw_grid<C: OutlineCanvas>(
+        &self,
+        canvas: &mut C,
+        color: C::Color,
+    ) -> Result<(), String> {
+        canvas.outline(&self.zone, color)?;
+        for t in self.children.iter() {
+            t.draw_grid(canvas, color)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Collidable, M: Default> Default for QuadTree<T, M> {
+    fn default() -> Self {
+        QuadTreeBuilder::default().build()
+    }
+}
+
+/// Collects values in a tree covering the default zone,
+/// see `QuadTreeBuilder`
+///
+/// To use another zone, build the tree and `extend` it.
+impl<T: Collidable, M: Default> FromIterator<T> for QuadTree<T, M> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> Self {
+        let mut qt = QuadTree::default();
+        qt.insert_many(values);
+        qt
+    }
+}
+
+/// Inserts the values with `insert_many`
+impl<T: Collidable, M: Default> Extend<T> for QuadTree<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        self.insert_many(values);
+    }
+}
+
+/// Draws the zones of the nodes and the values on any
+/// SDL2 canvas, be it a window or an offscreen surface
+#[cfg(feature = "sdl2")]
+impl<RT, T, M> Drawable<Canvas<RT>> for QuadTree<T, M>
+where
+    RT: RenderTarget,
+    T: Collidable + Drawable<Canvas<RT>>,
+{
+    fn draw(&self, canvas: &mut Canvas<RT>) -> Result<(), String> {
+        self.draw_node(canvas, 0)
+    }
+}
+
+#[cfg(feature = "sdl2")]
+impl<RT: RenderTarget> OutlineCanvas for Canvas<RT> {
+    type Color = Color;
+
+    fn outline(&mut self, zone: &AABB, color: Color) -> Result<(), String> {
+        self.set_draw_color(color);
+        self.draw_rect(Rect::from(zone))
+    }
+}
+
+/// Outline colors of the nodes, indexed by depth
+#[cfg(feature = "sdl2")]
+const DEPTH_COLORS: [(u8, u8, u8); 6] = [
+    (230, 230, 230),
+    (230, 80, 80),
+    (240, 170, 50),
+    (220, 220, 60),
+    (80, 200, 90),
+    (70, 140, 230),
+];
+
+/// Outline color of a node at `depth`, cycling
+/// through `DEPTH_COLORS` past its last color
+#[cfg(feature = "sdl2")]
+fn depth_color(depth: u32) -> Color {
+    let (r, g, b) = DEPTH_COLORS[depth as usize % DEPTH_COLORS.len()];
+    Color::RGB(r, g, b)
+}
+
+#[cfg(feature = "sdl2")]
+impl<T: Collidable, M> QuadTree<T, M> {
+    /// Draws the subtree, this node being at `depth`
+    ///
+    /// Nodes are outlined with the color of their depth,
+    /// so that a node keeps its color from frame to frame.
+    fn draw_node<RT>(&self, canvas: &mut Canvas<RT>, depth: u32) -> Result<(), String>
+    where
+        RT: RenderTarget,
+        T: Drawable<Canvas<RT>>,
+    {
+        canvas.outline(&self.zone, depth_color(depth))?;
+
+        for v in self.live_values() {
+            v.draw(canvas)?;
+        }
+
+        for t in self.children.iter() {
+            t.draw_node(canvas, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Scalar, T: Collidable<S> + Dynamic, M: Default> QuadTree<T, M, S> {
+    /// Updates every value of the subtree
+    ///
+    /// Moved values are re-homed as locally as possible: a value
+    /// that left the zone of its node is handed to the parent,
+    /// which inserts it back if the value is inside its own zone,
+    /// or hands it to its own parent otherwise.
+    /// Values that left this node's zone are pushed in `escaped`.
+    ///
+    /// Values moving in or out of a watched zone push
+    /// the corresponding events in `events`.
+    fn update_node(
+        &mut self,
+        delta: &Duration,
+        watches: &Watches<S>,
+        escaped: &mut Vec<(T, u64)>,
+        events: &mut Vec<ZoneEvent<S>>,
+    ) -> bool {
+        let mut changed = false;
+        self.centroid = None;
+        let mut displaced = Vec::new();
+
+        for t in &mut self.children {
+            changed = t.update_node(delta, watches, &mut displaced, events) || changed;
+        }
+
+        let mut moved = Vec::new();
+
+        for (i, v) in self.values.iter_mut().enumerate() {
+            if self.tombstones[i] {
+                continue;
+            }
+            let old = *v.bounding_box();
+            if v.update(delta) {
+                changed = true;
+                moved.push(i);
+                if !watches.is_empty() {
+                    watches.moved(&old, v.bounding_box(), events);
+                }
+            }
+        }
+
+        self.rehome(moved, displaced, escaped);
+
+        changed
+    }
+
+    /// Updates every value of the tree without re-homing
+    /// the values which moved
+    ///
+    /// The nodes holding moved values are flagged for a later
+    /// `reindex_moved`, until which queries may miss the values
+    /// that left their node. Returns whether a value moved.
+    pub fn step(&mut self, delta: &Duration) -> bool {
+        self.centroid = None;
+        let mut changed = false;
+        for t in &mut self.children {
+            changed = t.step(delta) || changed;
+        }
+
+        for (i, v) in self.values.iter_mut().enumerate() {
+            if !self.tombstones[i] && v.update(delta) {
+                changed = true;
+            }
+        }
+
+        self.stale = self.stale || changed;
+        changed
+    }
+
+    /// Updates the tree like `Dynamic::update` and also returns
+    /// the values entering or leaving the zones registered
+    /// with `watch_zone`
+    pub fn update_watched(&mut self, delta: &Duration) -> (bool, Vec<ZoneEvent<S>>) {
+        let mut escaped = Vec::new();
+        let mut watches = std::mem::take(&mut self.watches);
+        let mut events = watches.take_pending();
+        let changed = self.update_node(delta, &watches, &mut escaped, &mut events);
+        self.watches = watches;
+
+        // Values out of the whole tree are kept by the root
+        for (v, seq) in escaped {
+            self.insert_root_entry(v, seq);
+        }
+
+        (changed, events)
+    }
+}
+
+/// Updates every value of the tree
+///
+/// Nodes have no link to their parent: values leaving the zone
+/// of their node are returned up the recursion instead, until
+/// a node holding them inserts them back, so a value can move
+/// to any branch of the tree. Values leaving the whole tree are
+/// kept by the root, or grow it, see `QuadTreeBuilder::grow`.
+impl<S: Scalar, T: Collidable<S> + Dynamic, M: Default> Dynamic for QuadTree<T, M, S> {
+    fn update(&mut self, delta: &Duration) -> bool {
+        self.update_watched(delta).0
     }
 }
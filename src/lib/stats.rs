@@ -0,0 +1,52 @@
+use super::traits::*;
+use super::QuadTree;
+
+/// Shape of a tree and spread of its values,
+/// see `QuadTree::stats`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct QuadTreeStats {
+    /// Depth of the deepest node, the root being 0
+    pub max_depth_used: u32,
+    /// Number of nodes, including the root
+    pub node_count: usize,
+    /// Number of leaves
+    pub leaf_count: usize,
+    /// Number of values of the tree
+    pub total_values: usize,
+    /// Number of values held by the nodes of each depth
+    pub values_per_depth: Vec<usize>,
+    /// Number of values of the fullest node
+    pub max_values_in_any_node: usize,
+}
+
+impl<T: Collidable, M: Default> QuadTree<T, M> {
+    /// Computes statistics on the tree in a single traversal
+    ///
+    /// Meant to tune `max_values` and `max_depth`: many values
+    /// held near the root hint at values too big for the leaves,
+    /// many leaves holding few values at over-subdivision.
+    pub fn stats(&self) -> QuadTreeStats {
+        let mut stats = QuadTreeStats::default();
+        self.collect_stats(0, &mut stats);
+        stats
+    }
+
+    fn collect_stats(&self, depth: u32, stats: &mut QuadTreeStats) {
+        let count = self.live_values().count();
+        stats.max_depth_used = stats.max_depth_used.max(depth);
+        stats.node_count += 1;
+        stats.leaf_count += self.is_leaf() as usize;
+        stats.total_values += count;
+        stats.max_values_in_any_node = stats.max_values_in_any_node.max(count);
+
+        let d = depth as usize;
+        if stats.values_per_depth.len() <= d {
+            stats.values_per_depth.resize(d + 1, 0);
+        }
+        stats.values_per_depth[d] += count;
+
+        for t in self.children.iter() {
+            t.collect_stats(depth + 1, stats);
+        }
+    }
+}
@@ -0,0 +1,1920 @@
+use std::time::Duration;
+
+use super::geometry::{AABBf, Coord, Quadrant, Quadrant::*, Size, AABB};
+use super::traits::*;
+use super::watch::{Watches, ZoneEvent};
+use super::QuadTree;
+
+/// Value of the test trees, moving by `(dx, dy)` on each update
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Val {
+    pub bbox: AABB,
+    pub tag: u32,
+    pub dx: Coord,
+    pub dy: Coord,
+}
+
+/// Still value with tag 0
+pub(crate) fn val(x: Coord, y: Coord, w: Size, h: Size) -> Val {
+    Val {
+        bbox: AABB { x, y, w, h },
+        tag: 0,
+        dx: 0,
+        dy: 0,
+    }
+}
+
+impl Val {
+    pub fn tagged(mut self, tag: u32) -> Val {
+        self.tag = tag;
+        self
+    }
+
+    pub fn moving(mut self, dx: Coord, dy: Coord) -> Val {
+        self.dx = dx;
+        self.dy = dy;
+        self
+    }
+}
+
+impl Collidable for Val {
+    fn bounding_box(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+impl CollidableMut for Val {
+    fn bounding_box_mut(&mut self) -> &mut AABB {
+        &mut self.bbox
+    }
+}
+
+impl Dynamic for Val {
+    fn update(&mut self, _delta: &Duration) -> bool {
+        self.bbox.translate(self.dx, self.dy);
+        self.dx != 0 || self.dy != 0
+    }
+}
+
+/// Default 256x256 tree holding `values`
+fn tree_of(values: Vec<Val>) -> QuadTree<Val> {
+    let mut qt = QuadTree::default();
+    for v in values {
+        qt.insert(v);
+    }
+    qt
+}
+
+/// Tags of `values`, sorted
+fn tags(values: Vec<&Val>) -> Vec<u32> {
+    let mut tags: Vec<u32> = values.iter().map(|v| v.tag).collect();
+    tags.sort_unstable();
+    tags
+}
+
+#[test]
+fn query_excluding_skips_excluded_boxes() {
+    let qt = tree_of(vec![
+        val(10, 10, 5, 5).tagged(1),
+        val(40, 10, 5, 5).tagged(2),
+        val(10, 40, 5, 5).tagged(3),
+        val(40, 40, 5, 5).tagged(4),
+        val(200, 200, 5, 5).tagged(5),
+    ]);
+    let region = AABB::from((0, 0, 64, 64));
+    let exclude = [AABB::from((40, 10, 5, 5)), AABB::from((10, 40, 5, 5))];
+
+    assert_eq!(tags(qt.query_excluding(&region, &exclude)), vec![1, 4]);
+    assert_eq!(tags(qt.query_excluding(&region, &[])), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn node_meta_tags_a_leaf() {
+    let mut qt = QuadTree::<Val, String>::default();
+    qt.insert(val(10, 10, 5, 5));
+    qt.insert(val(200, 200, 5, 5));
+    assert!(!qt.is_leaf());
+
+    *qt.node_meta_mut(10, 10).unwrap() = String::from("water");
+
+    assert_eq!(qt.node_meta(12, 12).map(String::as_str), Some("water"));
+    assert_eq!(qt.node_meta(200, 200).map(String::as_str), Some(""));
+    assert_eq!(qt.node_meta(300, 10), None);
+    assert!(qt.node_meta_mut(-1, 10).is_none());
+}
+
+#[test]
+fn occupancy_ratio_counts_populated_leaves() {
+    assert_eq!(tree_of(vec![]).occupancy_ratio(), 0.);
+
+    // Two of the four quadrants are populated
+    let qt = tree_of(vec![val(10, 10, 5, 5), val(200, 200, 5, 5)]);
+    assert_eq!(qt.node_count(), 5);
+    assert_eq!(qt.occupancy_ratio(), 0.5);
+}
+
+/// Tag pairs of `pairs`, each ordered and the list sorted
+fn tag_pairs(pairs: impl Iterator<Item = (u32, u32)>) -> Vec<(u32, u32)> {
+    let mut res: Vec<(u32, u32)> = pairs.map(|(a, b)| (a.min(b), a.max(b))).collect();
+    res.sort_unstable();
+    res
+}
+
+/// Grid of `n` by `n` boxes of size `size`, every `step` pixels
+fn grid(n: u32, step: Coord, size: Size) -> Vec<Val> {
+    (0..n * n)
+        .map(|i| {
+            let (x, y) = ((i % n) as Coord * step, (i / n) as Coord * step);
+            val(x, y, size, size).tagged(i)
+        })
+        .collect()
+}
+
+#[test]
+fn overlapping_pairs_are_unique_and_distinct() {
+    let values = grid(8, 20, 24);
+    let qt = tree_of(values.clone());
+
+    let pairs = tag_pairs(qt.iter_overlapping_pairs().map(|(a, b)| (a.tag, b.tag)));
+    let mut expected = Vec::new();
+    for (i, a) in values.iter().enumerate() {
+        for b in values[i + 1..].iter() {
+            if a.bbox.intersects(&b.bbox) {
+                expected.push((a.tag, b.tag));
+            }
+        }
+    }
+    let expected = tag_pairs(expected.into_iter());
+
+    assert!(pairs.iter().all(|(a, b)| a != b));
+    let mut unique = pairs.clone();
+    unique.dedup();
+    assert_eq!(unique.len(), pairs.len());
+    assert_eq!(pairs, expected);
+}
+
+#[test]
+fn raycast_finds_first_matching_hit() {
+    let qt = tree_of(vec![
+        val(20, 10, 5, 5).tagged(1),
+        val(60, 10, 5, 5).tagged(2),
+        val(100, 8, 5, 5).tagged(3),
+        val(140, 10, 5, 5).tagged(4),
+        val(60, 100, 5, 5).tagged(6),
+    ]);
+
+    let hits: Vec<u32> = qt.raycast_iter(0., 12., 1., 0.).map(|v| v.tag).collect();
+    assert_eq!(hits, vec![1, 2, 3, 4]);
+
+    let even = qt.raycast_iter(0., 12., 1., 0.).find(|v| v.tag % 2 == 0);
+    assert_eq!(even.map(|v| v.tag), Some(2));
+    let none = qt.raycast_iter(0., 12., 1., 0.).find(|v| v.tag > 5);
+    assert!(none.is_none());
+}
+
+#[test]
+fn raycast_hits_values_out_of_the_root_zone() {
+    let qt = tree_of(vec![val(300, 10, 5, 5).tagged(1), val(10, 10, 5, 5)]);
+
+    let hit = qt.raycast_iter(290., 12., 1., 0.).next();
+    assert_eq!(hit.map(|v| v.tag), Some(1));
+}
+
+/// Leaf root holding values tagged 0 to 5, in order
+fn leaf_of_six() -> QuadTree<Val> {
+    let mut qt = QuadTree::with_bounds(AABB::from((0, 0, 256, 256)), 1, 0);
+    for i in 0..6 {
+        qt.insert(val(10 * i, 0, 5, 5).tagged(i as u32));
+    }
+    qt
+}
+
+#[test]
+fn removal_keeps_order_when_order_preserving() {
+    let mut qt = leaf_of_six();
+    assert!(qt.remove(&val(10, 0, 5, 5).tagged(1)).is_some());
+
+    let order: Vec<u32> = qt.iter().map(|v| v.tag).collect();
+    assert_eq!(order, vec![0, 2, 3, 4, 5]);
+}
+
+#[test]
+fn removal_swaps_when_not_order_preserving() {
+    let mut qt = leaf_of_six();
+    qt.order_preserving(false);
+    assert!(qt.remove(&val(10, 0, 5, 5).tagged(1)).is_some());
+
+    // The last value takes the place of the removed one
+    let order: Vec<u32> = qt.iter().map(|v| v.tag).collect();
+    assert_eq!(order, vec![0, 5, 2, 3, 4]);
+    assert_eq!(tags(qt.iter().collect()), vec![0, 2, 3, 4, 5]);
+}
+
+#[test]
+fn retain_flags_values_without_moving_them() {
+    let mut qt = leaf_of_six();
+    qt.retain(&mut |v| v.tag % 2 == 0);
+
+    assert_eq!(qt.values.len(), 6);
+    assert_eq!(tags(qt.iter().collect()), vec![0, 2, 4]);
+    assert_eq!(qt.len(), 3);
+
+    qt.vacuum();
+    assert_eq!(qt.values.len(), 3);
+    assert_eq!(tags(qt.iter().collect()), vec![0, 2, 4]);
+}
+
+#[test]
+fn min_distance_from_nearest_other_box() {
+    let qt = tree_of(vec![
+        val(10, 10, 10, 10),
+        val(50, 14, 10, 10),
+        val(23, 24, 5, 5),
+        val(200, 200, 10, 10),
+    ]);
+
+    // 3 right and 4 down of (10, 10, 10, 10), the equal box being ignored
+    assert_eq!(
+        qt.min_distance_from(&AABB::from((10, 10, 10, 10))),
+        Some(5.)
+    );
+    assert_eq!(qt.min_distance_from(&AABB::from((25, 10, 5, 5))), Some(5.));
+    assert_eq!(
+        tree_of(vec![]).min_distance_from(&AABB::from((0, 0, 1, 1))),
+        None
+    );
+}
+
+/// One frame of a 60 FPS loop
+const FRAME: Duration = Duration::from_millis(16);
+
+#[test]
+fn update_rehomes_into_a_sibling_below_the_root() {
+    let mut qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1).moving(60, 0),
+        val(20, 100, 4, 4).tagged(2),
+        val(200, 200, 4, 4).tagged(3),
+    ]);
+    assert_eq!(
+        qt.zone_of_path(&[TopLeft, TopLeft]),
+        Some(AABB::from((0, 0, 64, 64)))
+    );
+
+    // Updating the top-left quadrant alone, as the root does:
+    // nothing is handed back to the root
+    let (mut escaped, mut events) = (Vec::new(), Vec::new());
+    qt.children[0].update_node(&FRAME, &Watches::default(), &mut escaped, &mut events);
+    assert!(escaped.is_empty());
+
+    let top_right = &qt.children[0].children[TopRight as usize];
+    assert_eq!(tags(top_right.iter().collect()), vec![1]);
+    assert_eq!(
+        top_right.iter().next().unwrap().bbox,
+        AABB::from((70, 10, 4, 4))
+    );
+    assert!(qt.children[0].children[TopLeft as usize].is_empty());
+}
+
+#[test]
+fn zone_of_path_follows_quadrants() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4),
+        val(130, 130, 4, 4),
+        val(200, 200, 4, 4),
+    ]);
+    let zone = *qt.zone();
+    let expected = Quadrant::quadrant_bbox(&Quadrant::quadrant_bbox(&zone, BottomRight), TopLeft);
+
+    assert_eq!(qt.zone_of_path(&[BottomRight, TopLeft]), Some(expected));
+    assert_eq!(expected, AABB::from((128, 128, 64, 64)));
+    assert_eq!(qt.zone_of_path(&[]), Some(zone));
+    assert_eq!(qt.zone_of_path(&[TopLeft, TopLeft]), None);
+}
+
+#[test]
+fn limit_nodes_collapses_keeping_values() {
+    let mut qt = tree_of(grid(8, 30, 4));
+    let len = qt.len();
+    assert!(qt.node_count() > 20);
+
+    qt.limit_nodes(9);
+    assert!(qt.node_count() <= 9);
+    assert_eq!(qt.len(), len);
+    for v in grid(8, 30, 4) {
+        assert!(qt.contains(&v));
+    }
+
+    qt.limit_nodes(0);
+    assert_eq!(qt.node_count(), 1);
+    assert_eq!(qt.len(), len);
+}
+
+#[test]
+fn sorted_by_distance_orders_by_center() {
+    let qt = tree_of(vec![
+        val(200, 200, 10, 10).tagged(4),
+        val(40, 0, 10, 10).tagged(2),
+        val(0, 0, 10, 10).tagged(1),
+        val(100, 100, 10, 10).tagged(3),
+    ]);
+
+    let order: Vec<u32> = qt.sorted_by_distance(5, 5).iter().map(|v| v.tag).collect();
+    assert_eq!(order, vec![1, 2, 3, 4]);
+    let order: Vec<u32> = qt
+        .sorted_by_distance(250, 250)
+        .iter()
+        .map(|v| v.tag)
+        .collect();
+    assert_eq!(order, vec![4, 3, 2, 1]);
+}
+
+#[test]
+fn empty_like_copies_the_configuration() {
+    let zone = AABB::from((-100, 50, 512, 300));
+    let mut qt = QuadTree::<Val>::with_bounds(zone, 3, 5);
+    qt.loose = true;
+    qt.insert(val(0, 60, 4, 4));
+
+    let other = qt.empty_like();
+    assert!(other.is_empty());
+    assert!(other.is_leaf());
+    assert_eq!(*other.zone(), zone);
+    assert_eq!(other.max_values(), 3);
+    assert_eq!(other.max_depth(), 5);
+    assert!(other.loose);
+
+    let built: QuadTree<Val> = QuadTree::<Val>::builder()
+        .zone(zone)
+        .max_values(3)
+        .max_depth(5)
+        .build();
+    let built = built.empty_like();
+    assert_eq!(*built.zone(), zone);
+    assert_eq!(built.max_values(), 3);
+    assert_eq!(built.max_depth(), 5);
+}
+
+#[test]
+fn quadrant_iterators_cover_the_tree() {
+    let mut qt = tree_of(grid(6, 40, 4));
+    let count = |qt: &QuadTree<Val>| -> usize {
+        Quadrant::all()
+            .iter()
+            .map(|&q| qt.iter_quadrant(q).unwrap().count())
+            .sum()
+    };
+    assert_eq!(count(&qt), qt.len());
+
+    // Values held by the root are in no quadrant
+    qt.insert(val(120, 120, 16, 16));
+    assert_eq!(count(&qt), qt.len() - 1);
+
+    assert!(tree_of(vec![val(0, 0, 1, 1)])
+        .iter_quadrant(TopLeft)
+        .is_none());
+}
+
+#[test]
+fn common_ancestor_is_the_deepest_shared_node() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4),
+        val(100, 10, 4, 4),
+        val(10, 100, 4, 4),
+        val(200, 200, 4, 4),
+    ]);
+    let a = AABB::from((5, 5, 4, 4));
+    let b = AABB::from((20, 20, 4, 4));
+
+    // Both in the top-left quadrant of the top-left quadrant
+    let node = qt.common_ancestor(&a, &b);
+    assert_eq!(*node.zone(), AABB::from((0, 0, 64, 64)));
+    assert!(node.is_leaf());
+
+    let c = AABB::from((200, 20, 4, 4));
+    assert_eq!(*qt.common_ancestor(&a, &c).zone(), *qt.zone());
+    let d = AABB::from((100, 100, 4, 4));
+    assert_eq!(
+        *qt.common_ancestor(&a, &d).zone(),
+        AABB::from((0, 0, 128, 128))
+    );
+}
+
+#[test]
+fn count_in_radius_matches_a_full_scan() {
+    let qt = tree_of(grid(10, 25, 6));
+    let center = AABB::from((100, 80, 0, 0));
+
+    for &radius in &[0, 5, 30, 64, 150, 1000] {
+        let expected = qt
+            .iter()
+            .filter(|v| v.bbox.distance_to(&center) <= radius as f64)
+            .count();
+        assert_eq!(qt.count_in_radius(100, 80, radius), expected);
+    }
+    assert_eq!(qt.count_in_radius(100, 80, 1000), qt.len());
+}
+
+#[test]
+fn coverage_of_a_box_spanning_four_leaves() {
+    let qt = tree_of(vec![val(10, 10, 4, 4), val(200, 200, 4, 4)]);
+    let mut zones = qt.coverage_of(&AABB::from((100, 100, 50, 50)));
+    zones.sort_by_key(|z| (z.y, z.x));
+
+    let quadrants: Vec<AABB> = Quadrant::all()
+        .iter()
+        .map(|&q| Quadrant::quadrant_bbox(qt.zone(), q))
+        .collect();
+    assert_eq!(zones, quadrants);
+    assert_eq!(
+        qt.coverage_of(&AABB::from((10, 10, 4, 4))),
+        vec![quadrants[0]]
+    );
+}
+
+/// Zones of every node of `qt`, depth-first
+fn zones<M>(qt: &QuadTree<Val, M>) -> Vec<AABB> {
+    let mut res = vec![*qt.zone()];
+    for t in qt.children.iter() {
+        res.extend(zones(t));
+    }
+    res
+}
+
+#[test]
+fn reset_values_keeps_the_partition() {
+    let mut qt = tree_of(grid(6, 40, 4));
+    let before = zones(&qt);
+
+    // Clustered data which would have split differently
+    let items: Vec<Val> = (0..20).map(|i| val(i, i, 2, 2).tagged(i as u32)).collect();
+    qt.reset_values(items);
+
+    assert_eq!(zones(&qt), before);
+    assert_eq!(qt.len(), 20);
+    assert!(!qt.overfull_nodes().is_empty());
+    assert_eq!(qt.query_range(&AABB::from((0, 0, 10, 10))).len(), 11);
+}
+
+#[test]
+fn max_overlap_depth_of_stacked_and_disjoint_boxes() {
+    let stacked = tree_of(vec![
+        val(10, 10, 20, 20),
+        val(20, 15, 20, 20),
+        val(25, 25, 10, 10),
+        val(200, 200, 5, 5),
+    ]);
+    assert_eq!(stacked.max_overlap_depth(), 3);
+
+    let disjoint = tree_of(vec![
+        val(10, 10, 5, 5),
+        val(100, 10, 5, 5),
+        val(10, 100, 5, 5),
+    ]);
+    assert_eq!(disjoint.max_overlap_depth(), 1);
+    assert_eq!(tree_of(vec![]).max_overlap_depth(), 0);
+}
+
+#[test]
+fn clear_into_appends_every_value() {
+    let mut qt = tree_of(grid(4, 50, 4));
+    let zone = *qt.zone();
+    let mut sink = vec![val(0, 0, 1, 1).tagged(100)];
+
+    qt.clear_into(&mut sink);
+    assert_eq!(sink.len(), 17);
+    assert_eq!(sink[0].tag, 100);
+    assert_eq!(
+        tags(sink[1..].iter().collect()),
+        (0..16).collect::<Vec<u32>>()
+    );
+
+    assert!(qt.is_empty());
+    assert!(qt.is_leaf());
+    assert_eq!(*qt.zone(), zone);
+}
+
+#[test]
+fn recommended_depth_reaches_the_object_size() {
+    let world = AABB::from((0, 0, 1024, 1024));
+    assert_eq!(super::recommended_depth(&world, (16, 16)), 6);
+    assert_eq!(super::recommended_depth(&world, (2048, 2048)), 0);
+    assert_eq!(super::recommended_depth(&world, (16, 1024)), 6);
+    assert_eq!(
+        super::recommended_depth(&AABB::from((0, 0, 1000, 1000)), (16, 16)),
+        6
+    );
+}
+
+#[test]
+fn query_morton_sorts_by_morton_code() {
+    let qt = tree_of(vec![
+        val(100, 100, 4, 4).tagged(4),
+        val(0, 100, 4, 4).tagged(3),
+        val(100, 0, 4, 4).tagged(2),
+        val(0, 0, 4, 4).tagged(1),
+        val(200, 200, 4, 4).tagged(5),
+    ]);
+
+    let res = qt.query_morton(&AABB::from((0, 0, 128, 128)));
+    let order: Vec<u32> = res.iter().map(|v| v.tag).collect();
+    assert_eq!(order, vec![1, 2, 3, 4]);
+    assert!(res
+        .windows(2)
+        .all(|w| w[0].bbox.morton_code() <= w[1].bbox.morton_code()));
+}
+
+#[test]
+fn insert_would_split_full_leaves_only() {
+    let qt = tree_of(vec![val(10, 10, 4, 4), val(200, 200, 4, 4)]);
+
+    // The top-left leaf is full, the top-right one is empty
+    assert!(qt.insert_would_split(&AABB::from((20, 20, 4, 4))));
+    assert!(!qt.insert_would_split(&AABB::from((200, 20, 4, 4))));
+    // Straddling values stay in the root
+    assert!(!qt.insert_would_split(&AABB::from((120, 120, 16, 16))));
+
+    let mut leaf = QuadTree::<Val>::with_bounds(AABB::from((0, 0, 256, 256)), 1, 0);
+    leaf.insert(val(10, 10, 4, 4));
+    assert!(!leaf.insert_would_split(&AABB::from((20, 20, 4, 4))));
+}
+
+#[test]
+fn iter_with_depth_puts_small_boxes_deeper() {
+    let qt = tree_of(vec![
+        val(2, 2, 2, 2).tagged(1),
+        val(20, 20, 2, 2).tagged(2),
+        val(100, 100, 100, 100).tagged(3),
+        val(200, 10, 4, 4).tagged(4),
+    ]);
+    let depth = |tag| {
+        qt.iter_with_depth()
+            .into_iter()
+            .find(|(v, _)| v.tag == tag)
+            .map(|(_, d)| d)
+            .unwrap()
+    };
+
+    assert_eq!(depth(3), 0);
+    assert_eq!(depth(4), 1);
+    assert!(depth(1) > depth(4));
+    assert_eq!(depth(1), depth(2));
+    assert_eq!(qt.iter_with_depth().len(), 4);
+}
+
+/// Whether every value is in the deepest node it fits in:
+/// inside the zone of its node, below the root, and in no
+/// quadrant of its node unless that node is a leaf
+fn well_placed<T: Collidable, M: Default>(qt: &QuadTree<T, M>) -> bool {
+    qt.live_values()
+        .all(|v| qt.is_leaf() || qt.fits(v).is_none())
+        && qt
+            .children
+            .iter()
+            .all(|t| t.live_values().all(|v| t.belongs(v.bounding_box())) && well_placed(t))
+}
+
+#[test]
+fn transform_boxes_rehomes_scaled_values() {
+    let mut qt: QuadTree<Val> = QuadTree::<Val>::builder()
+        .max_values(1)
+        .max_depth(6)
+        .grow(true)
+        .build();
+    qt.insert_many(grid(4, 30, 8));
+    // Grows the root to 1024x1024
+    qt.insert(val(700, 700, 8, 8).tagged(16));
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 1024, 1024)));
+
+    qt.transform_boxes(|b| AABB::from((b.x * 2, b.y * 2, b.w * 2, b.h * 2)));
+
+    assert_eq!(qt.len(), 17);
+    assert!(well_placed(&qt));
+    for v in grid(4, 60, 16) {
+        assert_eq!(tags(qt.query_range(&v.bbox)), vec![v.tag]);
+    }
+    assert_eq!(tags(qt.query_point(1404, 1404)), vec![16]);
+}
+
+#[test]
+fn query_upper_bound_is_at_least_the_query_count() {
+    let qt = tree_of(grid(10, 25, 10));
+    for region in [
+        AABB::from((0, 0, 256, 256)),
+        AABB::from((30, 30, 40, 40)),
+        AABB::from((120, 5, 3, 200)),
+        AABB::from((300, 300, 10, 10)),
+    ] {
+        assert!(qt.query_upper_bound(&region) >= qt.query_range(&region).len());
+    }
+    assert_eq!(qt.query_upper_bound(&AABB::from((0, 0, 256, 256))), 100);
+}
+
+#[test]
+fn overfull_nodes_reports_full_leaves_at_max_depth() {
+    let mut qt = QuadTree::<Val>::with_bounds(AABB::from((0, 0, 256, 256)), 2, 1);
+    qt.insert_many(vec![
+        val(10, 10, 4, 4),
+        val(20, 20, 4, 4),
+        val(30, 30, 4, 4),
+        val(200, 200, 4, 4),
+    ]);
+
+    assert_eq!(qt.depth(), 1);
+    assert_eq!(qt.overfull_nodes(), vec![(AABB::from((0, 0, 128, 128)), 3)]);
+    assert!(tree_of(vec![]).overfull_nodes().is_empty());
+}
+
+#[test]
+fn centroid_cache_keeps_nearest_results() {
+    let plain = tree_of(grid(8, 30, 6));
+    let mut cached = plain.clone();
+    cached.build_centroid_cache();
+    assert_eq!(cached.centroid, Some((108., 108.)));
+
+    for (px, py) in [(0, 0), (100, 37), (255, 3), (-50, 300), (131, 131)] {
+        assert_eq!(plain.nearest(px, py), cached.nearest(px, py));
+    }
+
+    // A mutation invalidates the cache along its path
+    cached.insert(val(250, 250, 4, 4).tagged(64));
+    assert_eq!(cached.centroid, None);
+    assert_eq!(cached.nearest(254, 254).map(|v| v.tag), Some(64));
+
+    cached.build_centroid_cache();
+    let (cx, cy) = cached.centroid.unwrap();
+    assert_eq!((cx * 65., cy * 65.), (108. * 64. + 252., 108. * 64. + 252.));
+}
+
+#[test]
+fn values_in_quadrant_are_those_of_the_quadrant() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(200, 10, 4, 4).tagged(2),
+        val(10, 200, 4, 4).tagged(3),
+        val(200, 200, 4, 4).tagged(4),
+        val(210, 210, 4, 4).tagged(5),
+    ]);
+
+    assert_eq!(tags(qt.values_in_quadrant(TopLeft)), vec![1]);
+    assert_eq!(tags(qt.values_in_quadrant(TopRight)), vec![2]);
+    assert_eq!(tags(qt.values_in_quadrant(BottomLeft)), vec![3]);
+    assert_eq!(tags(qt.values_in_quadrant(BottomRight)), vec![4, 5]);
+}
+
+/// Value counting the calls to its `bounding_box`
+#[derive(Debug)]
+struct Counted<'a> {
+    bbox: AABB,
+    calls: &'a std::cell::Cell<usize>,
+}
+
+impl Collidable for Counted<'_> {
+    fn bounding_box(&self) -> &AABB {
+        self.calls.set(self.calls.get() + 1);
+        &self.bbox
+    }
+}
+
+#[test]
+fn any_in_region_stops_at_the_first_match() {
+    let calls = std::cell::Cell::new(0);
+    let mut qt: QuadTree<Counted> = QuadTree::default();
+    // Straddles the four quadrants, so is held by the root
+    qt.insert(Counted {
+        bbox: AABB::from((120, 120, 16, 16)),
+        calls: &calls,
+    });
+    for v in grid(8, 30, 6) {
+        qt.insert(Counted {
+            bbox: v.bbox,
+            calls: &calls,
+        });
+    }
+    let region = AABB::from((100, 100, 50, 50));
+
+    calls.set(0);
+    assert!(qt.any_in_region(&region));
+    assert_eq!(calls.get(), 1);
+
+    calls.set(0);
+    assert_eq!(qt.query_range(&region).len(), 5);
+    assert!(calls.get() > 5);
+
+    calls.set(0);
+    assert!(!qt.any_in_region(&AABB::from((235, 235, 10, 10))));
+}
+
+#[test]
+fn merged_boxes_joins_a_chain_and_keeps_a_lone_box() {
+    let qt = tree_of(vec![
+        val(10, 10, 20, 20),
+        val(25, 25, 20, 20),
+        // Touches the previous box only
+        val(45, 45, 10, 10),
+        val(200, 200, 5, 5),
+    ]);
+
+    let mut merged = qt.merged_boxes();
+    merged.sort_by_key(|b| (b.x, b.y));
+    assert_eq!(
+        merged,
+        vec![AABB::from((10, 10, 45, 45)), AABB::from((200, 200, 5, 5))]
+    );
+}
+
+#[test]
+fn update_one_rehomes_across_a_boundary() {
+    let mut qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(100, 100, 4, 4).tagged(2),
+        val(200, 200, 4, 4).tagged(3),
+    ]);
+    let moved = qt.update_one(&AABB::from((100, 100, 4, 4)), |v| {
+        v.bbox.translate(100, 0);
+        true
+    });
+
+    assert!(moved);
+    assert!(well_placed(&qt));
+    assert_eq!(qt.len(), 3);
+    assert_eq!(tags(qt.values_in_quadrant(TopRight)), vec![2]);
+    assert!(qt.values_in_quadrant(TopLeft).iter().all(|v| v.tag == 1));
+    assert!(!qt.update_one(&AABB::from((100, 100, 4, 4)), |_| true));
+}
+
+#[test]
+fn size_histogram_buckets_by_area() {
+    let qt = tree_of(vec![
+        val(10, 10, 1, 1),
+        val(20, 20, 2, 2),
+        val(30, 30, 4, 4),
+        val(200, 10, 4, 4),
+        val(10, 200, 5, 5),
+        val(100, 100, 40, 40),
+    ]);
+
+    // Boundaries are inclusive: areas 4 and 16 fall in their bucket
+    assert_eq!(qt.size_histogram(&[4, 16, 100]), vec![2, 2, 1, 1]);
+    assert_eq!(qt.size_histogram(&[]), vec![6]);
+}
+
+#[test]
+fn clone_region_holds_the_queried_values_independently() {
+    let qt = tree_of(grid(8, 30, 6));
+    let region = AABB::from((20, 20, 70, 50));
+
+    let mut part = qt.clone_region(&region);
+    assert_eq!(part.len(), qt.query_range(&region).len());
+    assert_eq!(
+        tags(part.query_range(&region)),
+        tags(qt.query_range(&region))
+    );
+    assert_eq!(part.zone(), qt.zone());
+
+    part.clear();
+    part.insert(val(0, 0, 4, 4).tagged(100));
+    assert_eq!(qt.len(), 64);
+    assert!(qt.iter().all(|v| v.tag != 100));
+}
+
+#[test]
+fn first_hit_moving_returns_the_nearer_box() {
+    let qt = tree_of(vec![
+        val(150, 10, 10, 10).tagged(1),
+        val(80, 12, 10, 10).tagged(2),
+        val(80, 100, 10, 10).tagged(3),
+    ]);
+    let start = AABB::from((10, 10, 10, 10));
+
+    assert_eq!(qt.first_hit_moving(&start, 200, 0).map(|v| v.tag), Some(2));
+    assert_eq!(qt.first_hit_moving(&start, -5, 0).map(|v| v.tag), None);
+    // Stops short of both boxes
+    assert_eq!(qt.first_hit_moving(&start, 40, 0).map(|v| v.tag), None);
+}
+
+#[test]
+fn reroot_returns_the_values_left_out() {
+    let mut qt = tree_of(grid(4, 60, 8));
+
+    let dropped = qt.reroot(AABB::from((0, 0, 128, 128)));
+
+    // Boxes at 120 end on the new edge and are kept
+    assert_eq!(
+        tags(dropped.iter().collect()),
+        vec![3, 7, 11, 12, 13, 14, 15]
+    );
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 128, 128)));
+    assert_eq!(
+        tags(qt.query_range(&AABB::from((0, 0, 128, 128)))),
+        vec![0, 1, 2, 4, 5, 6, 8, 9, 10]
+    );
+    assert!(well_placed(&qt));
+}
+
+#[test]
+fn insertion_order_survives_splits() {
+    let mut qt: QuadTree<Val> = QuadTree::<Val>::builder().track_order(true).build();
+    // Scattered so that later values split the leaves of earlier ones
+    let order = [9, 3, 14, 0, 7, 12, 5, 10, 1, 15, 6, 11, 2, 13, 4, 8];
+    let values = grid(4, 60, 8);
+    for &i in order.iter() {
+        qt.insert(values[i].clone());
+    }
+    assert!(qt.depth() >= 2);
+
+    let tags: Vec<usize> = qt
+        .iter_insertion_order()
+        .iter()
+        .map(|v| v.tag as usize)
+        .collect();
+    assert_eq!(tags, order);
+}
+
+#[test]
+fn drawing_cell_is_the_zone_of_the_storing_node() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4),
+        val(40, 40, 4, 4),
+        val(200, 10, 4, 4),
+        val(120, 120, 16, 16),
+    ]);
+
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((10, 10, 4, 4))),
+        Some(AABB::from((0, 0, 32, 32)))
+    );
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((40, 40, 4, 4))),
+        Some(AABB::from((32, 32, 32, 32)))
+    );
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((200, 10, 4, 4))),
+        Some(AABB::from((128, 0, 128, 128)))
+    );
+    // Straddlers are drawn in the root
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((120, 120, 16, 16))),
+        Some(AABB::from((0, 0, 256, 256)))
+    );
+    assert_eq!(qt.drawing_cell(&AABB::from((50, 50, 4, 4))), None);
+}
+
+/// Value counting the calls to its `bounding_box`
+/// and moving as `val` on each update
+struct Tracked<'a> {
+    val: Val,
+    calls: &'a std::cell::Cell<usize>,
+}
+
+impl Collidable for Tracked<'_> {
+    fn bounding_box(&self) -> &AABB {
+        self.calls.set(self.calls.get() + 1);
+        &self.val.bbox
+    }
+}
+
+impl CollidableMut for Tracked<'_> {
+    fn bounding_box_mut(&mut self) -> &mut AABB {
+        &mut self.val.bbox
+    }
+}
+
+impl Dynamic for Tracked<'_> {
+    fn update(&mut self, delta: &Duration) -> bool {
+        self.val.update(delta)
+    }
+}
+
+#[test]
+fn reindex_moved_leaves_still_subtrees_alone() {
+    let (still, moving) = (std::cell::Cell::new(0), std::cell::Cell::new(0));
+    let mut qt: QuadTree<Tracked> = QuadTree::default();
+    qt.insert(Tracked {
+        val: val(10, 10, 4, 4).moving(100, 0),
+        calls: &moving,
+    });
+    for (x, y) in [(200, 200), (230, 230), (140, 200)] {
+        qt.insert(Tracked {
+            val: val(x, y, 4, 4),
+            calls: &still,
+        });
+    }
+
+    assert!(qt.step(&FRAME));
+    still.set(0);
+    moving.set(0);
+    qt.reindex_moved();
+
+    assert_eq!(still.get(), 0);
+    assert!(moving.get() > 0);
+    assert!(well_placed(&qt));
+    assert_eq!(qt.query_point(112, 12).len(), 1);
+    assert_eq!(qt.len(), 4);
+}
+
+#[test]
+fn remove_from_a_deep_leaf_collapses_emptied_subtrees() {
+    let mut qt = QuadTree::<Val>::with_bounds(AABB::from((0, 0, 256, 256)), 2, 4);
+    qt.insert_many(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(20, 20, 4, 4).tagged(2),
+        val(40, 40, 4, 4).tagged(3),
+        val(200, 200, 4, 4).tagged(4),
+    ]);
+    assert_eq!(qt.depth(), 3);
+
+    let removed = qt.remove(&val(20, 20, 4, 4).tagged(2));
+    assert_eq!(removed.map(|v| v.tag), Some(2));
+    assert_eq!(tags(qt.query_range(qt.zone())), vec![1, 3, 4]);
+    // The top-left subtree still holds two values
+    assert_eq!(qt.depth(), 3);
+    assert!(well_placed(&qt));
+
+    assert!(qt.remove(&val(10, 10, 4, 4).tagged(1)).is_some());
+    assert_eq!(tags(qt.query_range(qt.zone())), vec![3, 4]);
+    assert_eq!(qt.node_count(), 5);
+    assert!(qt.children[TopLeft as usize].is_leaf());
+
+    assert!(qt.remove(&val(10, 10, 4, 4).tagged(1)).is_none());
+    assert!(qt.remove(&val(40, 40, 4, 4).tagged(3)).is_some());
+    assert!(qt.is_leaf());
+}
+
+#[test]
+fn query_range_in_one_quadrant_all_four_and_a_parent_value() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(200, 10, 4, 4).tagged(2),
+        val(10, 200, 4, 4).tagged(3),
+        val(200, 200, 4, 4).tagged(4),
+        // Straddles the four quadrants, so is held by the root
+        val(120, 120, 16, 16).tagged(5),
+    ]);
+
+    assert_eq!(tags(qt.query_range(&AABB::from((0, 0, 50, 50)))), vec![1]);
+    assert_eq!(
+        tags(qt.query_range(&AABB::from((5, 5, 210, 210)))),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert_eq!(tags(qt.query_range(&AABB::from((130, 130, 2, 2)))), vec![5]);
+}
+
+#[test]
+fn update_keeps_values_crossing_a_quadrant_boundary() {
+    let mut qt = tree_of(vec![
+        val(110, 10, 4, 4).tagged(1).moving(20, 0),
+        val(10, 10, 4, 4).tagged(2),
+        val(200, 200, 4, 4).tagged(3),
+    ]);
+
+    assert!(qt.update(&FRAME));
+
+    assert_eq!(qt.len(), 3);
+    assert!(well_placed(&qt));
+    assert_eq!(tags(qt.query_point(132, 12)), vec![1]);
+    assert_eq!(tags(qt.values_in_quadrant(TopRight)), vec![1]);
+    assert_eq!(tags(qt.values_in_quadrant(TopLeft)), vec![2]);
+}
+
+#[test]
+fn len_counts_straddlers_and_leaf_values() {
+    let empty = QuadTree::<Val>::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.len(), 0);
+
+    let mut values = grid(4, 60, 8);
+    // Both straddle quadrants and stay in inner nodes
+    values.push(val(120, 120, 16, 16));
+    values.push(val(60, 10, 10, 4));
+    let qt = tree_of(values);
+
+    assert_eq!(qt.len(), 18);
+    assert!(!qt.is_empty());
+    assert!(!qt.values.is_empty());
+}
+
+#[test]
+fn with_bounds_at_an_offset() {
+    let zone = AABB::from((-1000, 500, 200, 200));
+    let mut qt = QuadTree::<Val>::with_bounds(zone, 1, 3);
+    qt.insert(val(-990, 510, 4, 4).tagged(1));
+    qt.insert(val(-850, 650, 4, 4).tagged(2));
+
+    assert_eq!(qt.zone(), &zone);
+    assert_eq!((qt.max_values(), qt.max_depth()), (1, 3));
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((-990, 510, 4, 4))),
+        Some(AABB::from((-1000, 500, 100, 100)))
+    );
+    assert_eq!(
+        qt.drawing_cell(&AABB::from((-850, 650, 4, 4))),
+        Some(AABB::from((-900, 600, 100, 100)))
+    );
+    assert_eq!(tags(qt.query_point(-848, 652)), vec![2]);
+}
+
+#[test]
+#[should_panic]
+fn with_bounds_rejects_empty_nodes() {
+    QuadTree::<Val>::with_bounds(AABB::from((0, 0, 10, 10)), 0, 1);
+}
+
+#[test]
+fn nearest_is_found_in_a_sibling_subtree() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(130, 10, 4, 4).tagged(2),
+        val(10, 130, 4, 4).tagged(3),
+        val(200, 200, 4, 4).tagged(4),
+    ]);
+
+    // The point is in the top-left quadrant
+    assert_eq!(qt.nearest(125, 12).map(|v| v.tag), Some(2));
+    assert_eq!(qt.nearest(12, 125).map(|v| v.tag), Some(3));
+    assert_eq!(qt.nearest(11, 11).map(|v| v.tag), Some(1));
+    assert!(tree_of(vec![]).nearest(0, 0).is_none());
+}
+
+#[test]
+fn nearest_matches_a_full_scan() {
+    let qt = tree_of(grid(8, 31, 3));
+    for (px, py) in [(0, 0), (47, 47), (130, 2), (255, 255), (-40, 90)] {
+        let p = AABB::from((px, py, 0, 0));
+        let best = qt
+            .iter()
+            .map(|v| v.bbox.distance_to(&p))
+            .fold(f64::INFINITY, f64::min);
+        let found = qt.nearest(px, py).unwrap();
+        assert_eq!(found.bbox.distance_to(&p), best);
+    }
+}
+
+#[test]
+fn iter_yields_every_value_once() {
+    let mut values = grid(5, 50, 6);
+    values.push(val(120, 120, 16, 16).tagged(25));
+    let qt = tree_of(values.clone());
+
+    let all: Vec<&Val> = qt.iter().collect();
+    assert_eq!(all.len(), qt.len());
+    assert_eq!(tags(all), (0..26).collect::<Vec<u32>>());
+    assert!(values.iter().all(|v| qt.iter().any(|w| w == v)));
+}
+
+#[test]
+fn iter_mut_changes_values_in_place() {
+    let mut qt = tree_of(grid(4, 60, 6));
+    for v in qt.iter_mut() {
+        v.bbox.w *= 2;
+    }
+
+    assert_eq!(qt.len(), 16);
+    assert!(qt.iter().all(|v| v.bbox.w == 12 && v.bbox.h == 6));
+}
+
+#[test]
+fn clear_then_reinsert() {
+    let mut qt = tree_of(grid(4, 60, 6));
+    qt.clear();
+
+    assert!(qt.is_empty());
+    assert!(qt.is_leaf());
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 256, 256)));
+
+    qt.insert_many(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(200, 200, 4, 4).tagged(2),
+    ]);
+    assert_eq!(qt.len(), 2);
+    assert_eq!(qt.node_count(), 5);
+    assert_eq!(tags(qt.query_point(202, 202)), vec![2]);
+}
+
+/// Canvas recording what is drawn on it
+#[derive(Default)]
+struct Recorder {
+    boxes: Vec<AABB>,
+}
+
+impl Drawable<Recorder> for Val {
+    fn draw(&self, canvas: &mut Recorder) -> Result<(), String> {
+        canvas.boxes.push(self.bbox);
+        Ok(())
+    }
+}
+
+#[test]
+fn values_draw_on_any_canvas() {
+    let qt = tree_of(grid(3, 80, 6));
+    let mut canvas = Recorder::default();
+    for v in qt.iter() {
+        v.draw(&mut canvas).unwrap();
+    }
+
+    let mut expected: Vec<AABB> = grid(3, 80, 6).into_iter().map(|v| v.bbox).collect();
+    canvas.boxes.sort_by_key(|b| (b.y, b.x));
+    expected.sort_by_key(|b| (b.y, b.x));
+    assert_eq!(canvas.boxes, expected);
+}
+
+#[test]
+fn loose_trees_subdivide_center_straddlers() {
+    // Every box crosses one of the center lines of the root
+    let mut values = Vec::new();
+    for i in 0..8 {
+        values.push(val(125, 10 + 30 * i, 6, 6).tagged(i as u32));
+        values.push(val(10 + 30 * i, 125, 6, 6).tagged(8 + i as u32));
+    }
+    let mut strict: QuadTree<Val> = QuadTree::<Val>::builder().max_values(2).build();
+    let mut loose: QuadTree<Val> = QuadTree::<Val>::builder().max_values(2).loose(true).build();
+    strict.insert_many(values.clone());
+    loose.insert_many(values);
+
+    assert_eq!(strict.values.len(), 16);
+    assert!(loose.values.len() < 4);
+    assert!(loose.depth() >= 2);
+    assert!(well_placed(&loose));
+
+    let corner = AABB::from((0, 100, 60, 60));
+    assert_eq!(tags(loose.query_range(&corner)), vec![8, 9]);
+    assert_eq!(tags(strict.query_range(&corner)), vec![8, 9]);
+    assert!(loose.query_upper_bound(&corner) < loose.len() / 2);
+}
+
+#[test]
+fn for_each_in_range_calls_back_once_per_match() {
+    let mut values = grid(8, 30, 8);
+    values.push(val(120, 120, 16, 16));
+    let qt = tree_of(values);
+
+    for region in [
+        AABB::from((0, 0, 256, 256)),
+        AABB::from((25, 25, 40, 70)),
+        AABB::from((126, 126, 1, 1)),
+        AABB::from((300, 0, 10, 10)),
+    ] {
+        let mut calls = 0;
+        qt.for_each_in_range(&region, &mut |_| calls += 1);
+        assert_eq!(calls, qt.query_range(&region).len());
+        assert_eq!(
+            calls,
+            qt.iter().filter(|v| v.bbox.intersects(&region)).count()
+        );
+    }
+}
+
+#[test]
+fn collisions_in_a_leaf_across_depths_and_in_a_grid() {
+    let collision_tags =
+        |qt: &QuadTree<Val>| tag_pairs(qt.collisions().into_iter().map(|(a, b)| (a.tag, b.tag)));
+
+    // All in the root leaf
+    let mut qt = QuadTree::with_bounds(AABB::from((0, 0, 256, 256)), 4, 4);
+    qt.insert_many(vec![
+        val(10, 10, 6, 6).tagged(1),
+        val(14, 14, 6, 6).tagged(2),
+        val(200, 200, 4, 4).tagged(3),
+    ]);
+    assert!(qt.is_leaf());
+    assert_eq!(collision_tags(&qt), vec![(1, 2)]);
+
+    // The straddler is held by the root, the other value by a leaf
+    let qt = tree_of(vec![
+        val(120, 120, 16, 16).tagged(1),
+        val(130, 130, 4, 4).tagged(2),
+        val(10, 10, 4, 4).tagged(3),
+    ]);
+    assert_eq!(qt.values.len(), 1);
+    assert_eq!(collision_tags(&qt), vec![(1, 2)]);
+
+    let values = grid(6, 20, 22);
+    let qt = tree_of(values.clone());
+    let mut expected = Vec::new();
+    for (i, a) in values.iter().enumerate() {
+        for b in &values[i + 1..] {
+            if a.bbox.intersects(&b.bbox) {
+                expected.push((a.tag, b.tag));
+            }
+        }
+    }
+    expected.sort_unstable();
+    assert_eq!(collision_tags(&qt), expected);
+}
+
+#[test]
+fn clones_are_independent() {
+    let mut qt = tree_of(grid(4, 60, 6));
+    let snapshot = qt.clone();
+
+    qt.insert(val(250, 250, 4, 4).tagged(16));
+    qt.remove(&val(0, 0, 6, 6).tagged(0));
+    for v in qt.iter_mut() {
+        v.tag += 100;
+    }
+
+    assert_eq!(snapshot.len(), 16);
+    assert_eq!(
+        tags(snapshot.iter().collect()),
+        (0..16).collect::<Vec<u32>>()
+    );
+    assert!(snapshot.query_point(252, 252).is_empty());
+    assert_eq!(zones(&snapshot), zones(&tree_of(grid(4, 60, 6))));
+}
+
+#[test]
+fn query_point_inside_on_a_shared_edge_and_in_empty_space() {
+    let qt = tree_of(vec![
+        val(10, 10, 10, 10).tagged(1),
+        val(20, 10, 10, 10).tagged(2),
+        val(200, 200, 10, 10).tagged(3),
+        val(120, 120, 16, 16).tagged(4),
+    ]);
+
+    assert_eq!(tags(qt.query_point(15, 15)), vec![1]);
+    assert_eq!(tags(qt.query_point(20, 12)), vec![1, 2]);
+    assert_eq!(tags(qt.query_point(130, 130)), vec![4]);
+    assert!(qt.query_point(100, 50).is_empty());
+
+    let b: AABB = AABB::from((10, 10, 10, 10));
+    assert!(b.contains_point(20, 20));
+    assert!(!b.contains_point(21, 15));
+    assert_eq!(qt.query_point(20, 20).len(), 2);
+}
+
+#[test]
+#[cfg(feature = "sdl2")]
+fn depth_colors_are_stable() {
+    use super::{depth_color, DEPTH_COLORS};
+
+    let n = DEPTH_COLORS.len() as u32;
+    for depth in 0..2 * n {
+        assert_eq!(depth_color(depth), depth_color(depth));
+        assert_eq!(depth_color(depth), depth_color(depth + n));
+        assert_ne!(depth_color(depth), depth_color(depth + 1));
+    }
+}
+
+#[test]
+fn depth_and_node_count_of_empty_and_split_trees() {
+    let empty = QuadTree::<Val>::default();
+    assert_eq!((empty.depth(), empty.node_count()), (0, 1));
+
+    let qt = tree_of(vec![val(10, 10, 4, 4), val(200, 200, 4, 4)]);
+    assert_eq!((qt.depth(), qt.node_count()), (1, 5));
+}
+
+#[test]
+fn split_dispatches_values_to_children_and_parent() {
+    let mut qt = QuadTree::<Val>::with_bounds(AABB::from((0, 0, 256, 256)), 3, 1);
+    qt.insert_many(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(120, 120, 16, 16).tagged(2),
+        val(200, 200, 4, 4).tagged(3),
+    ]);
+    assert!(qt.is_leaf());
+
+    qt.insert(val(20, 20, 4, 4).tagged(4));
+
+    assert_eq!(qt.node_count(), 5);
+    assert_eq!(tags(qt.values.iter().collect()), vec![2]);
+    assert_eq!(
+        tags(qt.children[TopLeft as usize].values.iter().collect()),
+        vec![1, 4]
+    );
+    assert!(qt.children[TopRight as usize].values.is_empty());
+    assert!(qt.children[BottomLeft as usize].values.is_empty());
+    assert_eq!(
+        tags(qt.children[BottomRight as usize].values.iter().collect()),
+        vec![3]
+    );
+}
+
+#[test]
+fn insert_many_matches_sequential_inserts() {
+    let mut values = grid(7, 35, 9);
+    values.push(val(120, 120, 16, 16).tagged(49));
+    values.reverse();
+
+    let mut bulk = QuadTree::<Val>::default();
+    bulk.insert_many(values.clone());
+    let one_by_one = tree_of(values);
+
+    assert_eq!(bulk.len(), one_by_one.len());
+    for region in [
+        AABB::from((0, 0, 256, 256)),
+        AABB::from((30, 60, 50, 20)),
+        AABB::from((127, 127, 2, 2)),
+    ] {
+        assert_eq!(
+            tags(bulk.query_range(&region)),
+            tags(one_by_one.query_range(&region))
+        );
+    }
+}
+
+#[test]
+fn prune_merges_emptied_subtrees() {
+    let mut qt = QuadTree::<Val>::with_bounds(AABB::from((0, 0, 256, 256)), 2, 4);
+    qt.insert_many(grid(4, 60, 6));
+    assert!(!qt.is_leaf());
+
+    // Deleting does not merge nodes, unlike `remove`
+    for v in grid(4, 60, 6).iter().skip(1) {
+        assert!(qt.mark_deleted(&v.bbox));
+    }
+    qt.vacuum();
+    assert!(!qt.is_leaf());
+
+    qt.prune();
+    assert!(qt.is_leaf());
+    assert_eq!(tags(qt.query_range(&AABB::from((0, 0, 10, 10)))), vec![0]);
+    assert_eq!(qt.len(), 1);
+}
+
+#[test]
+fn collect_and_extend_match_inserts() {
+    let values = grid(5, 50, 6);
+    let collected: QuadTree<Val> = values.clone().into_iter().collect();
+    let mut extended = QuadTree::<Val>::default();
+    extended.extend(values.clone());
+    let inserted = tree_of(values);
+
+    let everything = AABB::from((0, 0, 256, 256));
+    let expected = tags(inserted.query_range(&everything));
+    assert_eq!(tags(collected.query_range(&everything)), expected);
+    assert_eq!(tags(extended.query_range(&everything)), expected);
+    assert_eq!(zones(&collected), zones(&inserted));
+    assert_eq!(collected.zone(), &everything);
+}
+
+#[test]
+fn retain_keeps_even_x_values() {
+    let mut qt = tree_of(grid(6, 35, 5));
+    qt.retain(&mut |v| v.bbox.x % 2 == 0);
+
+    let survivors: Vec<u32> = (0..36).filter(|i| i % 2 == 0).collect();
+    assert_eq!(tags(qt.iter().collect()), survivors);
+    assert_eq!(qt.len(), 18);
+    assert_eq!(
+        tags(qt.query_range(&AABB::from((30, 0, 50, 40)))),
+        vec![2, 8]
+    );
+    assert!(qt.query_range(&AABB::from((35, 0, 5, 256))).is_empty());
+}
+
+#[test]
+fn bounding_box_of_every_value() {
+    assert_eq!(tree_of(vec![]).bounding_box_of(), None);
+
+    let qt = tree_of(vec![
+        val(30, 40, 10, 10),
+        val(120, 120, 16, 16),
+        val(200, 20, 5, 50),
+        // Out of the zone, kept by the root
+        val(-20, 300, 4, 4),
+    ]);
+    assert_eq!(qt.bounding_box_of(), Some(AABB::from((-20, 20, 225, 284))));
+}
+
+#[test]
+fn growing_trees_grow_towards_negative_coordinates() {
+    let mut qt: QuadTree<Val> = QuadTree::<Val>::builder().grow(true).build();
+    qt.insert(val(10, 10, 4, 4).tagged(1));
+    qt.insert(val(-300, -20, 8, 8).tagged(2));
+
+    assert_eq!(qt.zone(), &AABB::from((-768, -256, 1024, 1024)));
+    assert_eq!(tags(qt.query_point(-296, -16)), vec![2]);
+    assert_eq!(tags(qt.query_point(12, 12)), vec![1]);
+    assert!(well_placed(&qt));
+}
+
+#[test]
+fn empty_zones_do_not_grow() {
+    for zone in [AABB::from((0, 0, 0, 0)), AABB::from((0, 0, 0, 64))] {
+        let mut qt: QuadTree<Val> = QuadTree::<Val>::builder().zone(zone).grow(true).build();
+        qt.insert(val(-10, 10, 4, 4).tagged(1));
+
+        assert_eq!(qt.zone(), &zone);
+        assert_eq!(tags(qt.values.iter().collect()), vec![1]);
+    }
+}
+
+#[test]
+fn rebuild_finds_values_moved_through_iter_mut() {
+    let mut qt = tree_of(grid(4, 60, 6));
+    for v in qt.iter_mut() {
+        v.bbox.translate(5, 5);
+    }
+
+    qt.rebuild();
+
+    assert_eq!(qt.len(), 16);
+    assert!(well_placed(&qt));
+    assert!(qt.query_range(&AABB::from((0, 0, 4, 4))).is_empty());
+    assert_eq!(tags(qt.query_point(8, 8)), vec![0]);
+    // (120, 120) moved across the center lines of the root
+    let center = AABB::from((125, 125, 6, 6));
+    assert_eq!(qt.drawing_cell(&center), Some(*qt.zone()));
+    assert_eq!(tags(qt.query_point(130, 130)), vec![10]);
+}
+
+#[test]
+fn contains_inserted_values_only() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4).tagged(1),
+        val(200, 200, 4, 4).tagged(2),
+        val(120, 120, 16, 16).tagged(3),
+    ]);
+
+    assert!(qt.contains(&val(10, 10, 4, 4).tagged(1)));
+    assert!(qt.contains(&val(200, 200, 4, 4).tagged(2)));
+    // Same shape and tag at another position
+    assert!(!qt.contains(&val(14, 10, 4, 4).tagged(1)));
+    assert!(!qt.contains(&val(10, 10, 4, 4).tagged(2)));
+    // Held by the root
+    assert!(qt.contains(&val(120, 120, 16, 16).tagged(3)));
+}
+
+#[test]
+fn query_segment_crossing_missing_and_clipping() {
+    let qt = tree_of(vec![
+        val(20, 50, 10, 10).tagged(1),
+        val(200, 45, 10, 10).tagged(2),
+        val(100, 150, 20, 20).tagged(3),
+    ]);
+
+    assert_eq!(tags(qt.query_segment(0, 55, 250, 55)), vec![1, 2]);
+    assert!(qt.query_segment(0, 100, 250, 120).is_empty());
+    // Through the top-right corner of the third box only
+    assert_eq!(tags(qt.query_segment(110, 140, 130, 160)), vec![3]);
+    assert!(qt.query_segment(112, 140, 130, 158).is_empty());
+}
+
+#[test]
+fn default_configuration() {
+    let qt = QuadTree::<Val>::default();
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 256, 256)));
+    assert_eq!(qt.max_values(), 1);
+    assert_eq!(qt.max_depth(), 4);
+}
+
+#[test]
+fn diagonal_mover_stays_indexed_at_every_step() {
+    let mut qt = tree_of(vec![
+        val(2, 2, 4, 4).tagged(1).moving(23, 23),
+        val(200, 20, 4, 4).tagged(2),
+        val(20, 200, 4, 4).tagged(3),
+        val(60, 60, 4, 4).tagged(4),
+    ]);
+
+    for step in 1..=11 {
+        assert!(qt.update(&FRAME));
+        let c = 2 + 23 * step + 2;
+        assert_eq!(qt.len(), 4);
+        assert!(well_placed(&qt));
+        assert!(tags(qt.query_point(c, c)).contains(&1), "step {}", step);
+    }
+    // Out of the tree, kept by the root
+    assert_eq!(tags(qt.values.iter().collect()), vec![1]);
+}
+
+#[test]
+fn from_refs_indexes_a_slice_in_place() {
+    let items = grid(4, 60, 6);
+    let qt: QuadTree<&Val> = QuadTree::from_refs(&items);
+
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 186, 186)));
+    let found = qt.query_range(&AABB::from((50, 50, 20, 20)));
+    assert_eq!(found.len(), 1);
+    assert!(std::ptr::eq(*found[0], &items[5]));
+    assert_eq!(qt.len(), items.len());
+}
+
+/// Canvas recording the outlined zones
+#[derive(Default)]
+struct Outlines {
+    zones: Vec<(AABB, u8)>,
+}
+
+impl OutlineCanvas for Outlines {
+    type Color = u8;
+
+    fn outline(&mut self, zone: &AABB, color: u8) -> Result<(), String> {
+        self.zones.push((*zone, color));
+        Ok(())
+    }
+}
+
+#[test]
+fn draw_grid_outlines_every_node() {
+    let qt = tree_of(grid(4, 60, 6));
+    let mut canvas = Outlines::default();
+    qt.draw_grid(&mut canvas, 7).unwrap();
+
+    assert_eq!(canvas.zones.len(), qt.node_count());
+    assert!(canvas.zones.iter().all(|&(_, c)| c == 7));
+    let drawn: Vec<AABB> = canvas.zones.iter().map(|&(z, _)| z).collect();
+    assert_eq!(drawn, zones(&qt));
+}
+
+#[test]
+fn stats_match_a_manual_count() {
+    let qt = tree_of(vec![
+        val(10, 10, 4, 4),
+        val(200, 200, 4, 4),
+        val(120, 120, 16, 16),
+        val(20, 20, 4, 4),
+    ]);
+
+    // The two top-left values are only apart in nodes of size 16
+    let stats = qt.stats();
+    assert_eq!(stats.max_depth_used, 4);
+    assert_eq!(stats.node_count, 17);
+    assert_eq!(stats.leaf_count, 13);
+    assert_eq!(stats.total_values, 4);
+    assert_eq!(stats.values_per_depth, vec![1, 1, 0, 0, 2]);
+    assert_eq!(stats.max_values_in_any_node, 1);
+}
+
+#[test]
+fn min_node_size_stops_subdivision_before_max_depth() {
+    let mut qt: QuadTree<Val> = QuadTree::<Val>::builder()
+        .max_depth(8)
+        .min_node_size(64)
+        .build();
+    qt.insert_many(vec![
+        val(10, 10, 2, 2),
+        val(20, 20, 2, 2),
+        val(30, 30, 2, 2),
+    ]);
+
+    // Nodes of 64 would split into quadrants of 32
+    assert_eq!(qt.depth(), 2);
+    assert_eq!(qt.overfull_nodes(), vec![(AABB::from((0, 0, 64, 64)), 3)]);
+    assert_eq!(qt.len(), 3);
+
+    let mut unbounded = tree_of(vec![]);
+    unbounded.insert_many(vec![
+        val(10, 10, 2, 2),
+        val(20, 20, 2, 2),
+        val(30, 30, 2, 2),
+    ]);
+    assert_eq!(unbounded.depth(), 4);
+}
+
+#[test]
+fn drain_empties_a_reusable_tree() {
+    let mut values = grid(4, 60, 6);
+    values.push(val(120, 120, 16, 16).tagged(16));
+    let mut qt = tree_of(values);
+    let len = qt.len();
+
+    let drained = qt.drain();
+    assert_eq!(drained.len(), len);
+    assert_eq!(
+        tags(drained.iter().collect()),
+        (0..17).collect::<Vec<u32>>()
+    );
+    assert!(qt.is_empty());
+    assert!(qt.is_leaf());
+
+    qt.insert(val(200, 200, 4, 4).tagged(1));
+    qt.insert(val(10, 10, 4, 4).tagged(2));
+    assert_eq!(tags(qt.query_point(202, 202)), vec![1]);
+    assert_eq!(qt.zone(), &AABB::from((0, 0, 256, 256)));
+}
+
+#[test]
+fn value_ids_follow_their_value_and_go_stale() {
+    let mut qt = QuadTree::<Val>::default().with_id_tracking();
+    let a = qt.insert_with_id(val(10, 10, 4, 4).tagged(1));
+    let b = qt.insert_with_id(val(10, 10, 4, 4).tagged(2));
+    // Splits the root, moving both values down
+    for v in grid(3, 90, 4).into_iter().skip(1) {
+        qt.insert(v.tagged(10));
+    }
+    assert!(!qt.is_leaf());
+
+    assert_eq!(qt.get(a).map(|v| v.tag), Some(1));
+    assert_eq!(qt.get(b).map(|v| v.tag), Some(2));
+    qt.get_mut(b).unwrap().tag = 3;
+
+    assert_eq!(qt.remove_by_id(a).map(|v| v.tag), Some(1));
+    assert_eq!(qt.len(), 9);
+    // `a` is stale, even once an equal value is inserted again
+    qt.insert_with_id(val(10, 10, 4, 4).tagged(1));
+    assert!(qt.get(a).is_none());
+    assert!(qt.remove_by_id(a).is_none());
+    assert_eq!(qt.get(b).map(|v| v.tag), Some(3));
+}
+
+#[test]
+fn value_ids_move_values() {
+    let mut qt = QuadTree::<Val>::default().with_id_tracking();
+    let a = qt.insert(val(10, 10, 4, 4).tagged(1));
+    let b = qt.insert(val(200, 200, 4, 4).tagged(2));
+
+    assert!(qt.update_element(a, AABB::from((100, 150, 4, 4))));
+    assert_eq!(tags(qt.query_point(102, 152)), vec![1]);
+    assert!(qt.query_point(12, 12).is_empty());
+
+    // Moving through `get_mut` re-homes the value on drop
+    qt.get_mut(b).unwrap().bbox = AABB::from((20, 20, 4, 4));
+    assert_eq!(tags(qt.query_point(22, 22)), vec![2]);
+    assert!(qt.query_point(202, 202).is_empty());
+    assert_eq!(qt.get(b).map(|v| v.bbox), Some(AABB::from((20, 20, 4, 4))));
+    // Values keep their place if their box is unchanged
+    qt.get_mut(b).unwrap().tag = 3;
+    assert_eq!(tags(qt.query_point(22, 22)), vec![3]);
+    assert_eq!(qt.len(), 2);
+
+    assert!(qt.remove_by_id(a).is_some());
+    assert!(!qt.update_element(a, AABB::from((0, 0, 1, 1))));
+}
+
+#[test]
+fn reused_value_id_slots_do_not_revive_stale_ids() {
+    let mut qt = QuadTree::<Val>::default().with_id_tracking();
+    let a = qt.insert(val(10, 10, 4, 4).tagged(1));
+    assert!(qt.remove_by_id(a).is_some());
+
+    // Takes the slot of `a`
+    let b = qt.insert(val(10, 10, 4, 4).tagged(2));
+    assert_ne!(a, b);
+    assert!(qt.get(a).is_none());
+    assert!(qt.get_mut(a).is_none());
+    assert!(qt.remove_by_id(a).is_none());
+    assert_eq!(qt.get(b).map(|v| v.tag), Some(2));
+}
+
+#[test]
+fn overlaps_any_deep_empty_and_parent_values() {
+    let qt = tree_of(vec![
+        val(2, 2, 2, 2),
+        val(20, 20, 2, 2),
+        val(120, 120, 16, 16),
+        val(200, 10, 4, 4),
+    ]);
+    assert!(qt.depth() >= 3);
+
+    assert!(qt.overlaps_any(&AABB::from((0, 0, 3, 3))));
+    assert!(!qt.overlaps_any(&AABB::from((40, 200, 30, 30))));
+    // Only the root holds a value there
+    assert!(qt.overlaps_any(&AABB::from((130, 130, 2, 2))));
+    assert!(!qt.overlaps_any(&AABB::from((140, 140, 2, 2))));
+}
+
+#[test]
+fn watched_zone_reports_moves_inserts_and_removals() {
+    use super::watch::ZoneEvent::{Entered, Left};
+
+    let mut qt = tree_of(vec![val(10, 100, 4, 4).moving(20, 0), val(200, 200, 4, 4)]);
+    let zone = AABB::from((25, 80, 40, 40));
+    let watch = qt.watch_zone(zone);
+    let other = qt.watch_zone(AABB::from((0, 0, 8, 8)));
+
+    // Moves in, then through, then out of the zone
+    assert_eq!(
+        qt.update_watched(&FRAME),
+        (true, vec![Entered(watch, AABB::from((30, 100, 4, 4)))])
+    );
+    assert_eq!(qt.update_watched(&FRAME).1, vec![]);
+    assert_eq!(
+        qt.update_watched(&FRAME).1,
+        vec![Left(watch, AABB::from((70, 100, 4, 4)))]
+    );
+
+    // Inserted and removed values are reported by the next update
+    qt.insert(val(50, 90, 4, 4).tagged(1));
+    qt.insert(val(150, 90, 4, 4).tagged(2));
+    assert!(qt.remove(&val(200, 200, 4, 4)).is_some());
+    assert_eq!(
+        qt.update_watched(&FRAME).1,
+        vec![Entered(watch, AABB::from((50, 90, 4, 4)))]
+    );
+    assert!(qt.remove(&val(50, 90, 4, 4).tagged(1)).is_some());
+    qt.insert(val(2, 2, 4, 4));
+    assert_eq!(
+        qt.update_watched(&FRAME).1,
+        vec![
+            Left(watch, AABB::from((50, 90, 4, 4))),
+            Entered(other, AABB::from((2, 2, 4, 4)))
+        ]
+    );
+
+    // Pending events of an unwatched zone are dropped
+    qt.insert(val(60, 90, 4, 4));
+    qt.unwatch(watch);
+    assert_eq!(qt.update_watched(&FRAME).1, vec![]);
+}
+
+#[test]
+fn frozen_deep_tree_answers_like_the_live_tree() {
+    let mut values = grid(10, 13, 4);
+    values.push(val(120, 120, 16, 16).tagged(100));
+    values.push(val(60, 60, 10, 10).tagged(101));
+    values.push(val(2, 2, 2, 2).tagged(102));
+    let mut qt = tree_of(values);
+    assert!(qt.depth() >= 3);
+
+    let frozen = qt.freeze();
+    assert_eq!(frozen.len(), qt.len());
+    let regions = [
+        AABB::from((0, 0, 256, 256)),
+        AABB::from((0, 0, 5, 5)),
+        AABB::from((20, 20, 30, 30)),
+        AABB::from((62, 62, 2, 2)),
+        AABB::from((100, 100, 40, 40)),
+        AABB::from((125, 0, 10, 256)),
+        AABB::from((200, 200, 50, 50)),
+    ];
+    for region in regions.iter() {
+        assert_eq!(
+            tags(frozen.query_aabb(region)),
+            tags(qt.query_range(region))
+        );
+    }
+    assert_eq!(tags(frozen.query_aabb(&regions[1])), vec![0, 102]);
+
+    // The snapshot does not follow the tree
+    qt.insert(val(3, 3, 1, 1).tagged(103));
+    assert_eq!(tags(frozen.query_aabb(&regions[1])), vec![0, 102]);
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn split_report_records_depth_and_values_kept() {
+    let mut qt: QuadTree<Val> = QuadTree::with_bounds(AABB::from((0, 0, 256, 256)), 2, 3);
+    qt.insert(val(120, 120, 16, 16));
+    qt.insert(val(10, 10, 4, 4));
+    assert!(qt.split_distribution_report().splits.is_empty());
+
+    // The root splits keeping the straddler
+    qt.insert(val(20, 20, 4, 4));
+    assert_eq!(qt.split_distribution_report().splits, vec![(0, 2, 1)]);
+
+    // Its top-left child splits keeping none, and the
+    // grandchild keeps the value straddling its quadrants
+    qt.insert(val(30, 30, 4, 4));
+    assert_eq!(
+        qt.split_distribution_report().splits,
+        vec![(0, 2, 1), (1, 2, 0)]
+    );
+    assert_eq!(qt.depth(), 2);
+}
+
+#[test]
+fn dot_export_of_a_two_level_tree() {
+    let qt = tree_of(vec![val(10, 10, 5, 5), val(200, 200, 5, 5)]);
+    let dot = qt.to_dot();
+    let lines: Vec<&str> = dot.lines().collect();
+
+    assert_eq!(lines.iter().filter(|l| l.contains(" [label=")).count(), 5);
+    assert_eq!(lines.iter().filter(|l| l.contains(" -- ")).count(), 4);
+    assert!(dot.starts_with("graph quadtree {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    assert!(dot.contains("    n0 [label=\"0,0 256x256\\n0 values\", pos=\"128,128!\"];\n"));
+    let positions: Vec<&str> = lines
+        .iter()
+        .filter_map(|l| l.split("pos=\"").nth(1))
+        .map(|p| p.trim_end_matches("\"];"))
+        .collect();
+    assert_eq!(
+        positions,
+        vec!["128,128!", "64,64!", "192,64!", "64,192!", "192,192!"]
+    );
+    for child in 1..5 {
+        assert!(lines.contains(&format!("    n0 -- n{};", child).as_str()));
+    }
+}
+
+/// Value equal to the values with the same `id`, wherever they are
+#[derive(Debug)]
+struct Entity {
+    id: u32,
+    bbox: AABB,
+}
+
+impl PartialEq for Entity {
+    fn eq(&self, other: &Entity) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Collidable for Entity {
+    fn bounding_box(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+#[test]
+fn spatial_interpolate_blends_matched_values() {
+    let entity = |id, x, y, w, h| Entity {
+        id,
+        bbox: AABB::from((x, y, w, h)),
+    };
+    let a: QuadTree<Entity> = vec![
+        entity(1, 0, 0, 10, 10),
+        entity(2, 100, 200, 4, 4),
+        entity(3, 50, 50, 5, 5),
+    ]
+    .into_iter()
+    .collect();
+    let b: QuadTree<Entity> = vec![
+        entity(2, 140, 100, 8, 4),
+        entity(1, 20, 40, 10, 30),
+        entity(4, 0, 0, 1, 1),
+    ]
+    .into_iter()
+    .collect();
+
+    let blend = |t| {
+        let mut res = QuadTree::spatial_interpolate(&a, &b, t);
+        res.sort_by_key(|(old, _)| old.x);
+        res
+    };
+    let (one, two) = (AABB::from((0, 0, 10, 10)), AABB::from((100, 200, 4, 4)));
+
+    assert_eq!(blend(0.), vec![(one, one), (two, two)]);
+    assert_eq!(
+        blend(0.5),
+        vec![
+            (one, AABB::from((10, 20, 10, 20))),
+            (two, AABB::from((120, 150, 6, 4)))
+        ]
+    );
+    assert_eq!(
+        blend(1.),
+        vec![
+            (one, AABB::from((20, 40, 10, 30))),
+            (two, AABB::from((140, 100, 8, 4)))
+        ]
+    );
+}
+
+/// Value with a float box, moving by `vx` on each update
+#[derive(Debug, Clone, PartialEq)]
+struct Particle {
+    bbox: AABBf,
+    vx: f32,
+}
+
+fn particle(x: f32, y: f32, vx: f32) -> Particle {
+    Particle {
+        bbox: AABBf::from((x, y, 0.5, 0.5)),
+        vx,
+    }
+}
+
+impl Collidable<f32> for Particle {
+    fn bounding_box(&self) -> &AABBf {
+        &self.bbox
+    }
+}
+
+impl Dynamic for Particle {
+    fn update(&mut self, _delta: &Duration) -> bool {
+        self.bbox.translate(self.vx, 0.);
+        self.vx != 0.
+    }
+}
+
+#[test]
+fn float_tree_splits_queries_and_updates() {
+    let mut qt: QuadTree<Particle, (), f32> =
+        QuadTree::with_bounds(AABBf::from((0., 0., 8., 8.)), 2, 4);
+    qt.insert(particle(0.25, 0.25, 0.));
+    qt.insert(particle(0.75, 0.25, 0.));
+    qt.insert(particle(1.25, 0.25, 3.5));
+    qt.insert(particle(6.5, 6.5, 0.));
+    // Straddles the center, kept by the root
+    qt.insert(Particle {
+        bbox: AABBf::from((3.75, 3.75, 0.5, 0.5)),
+        vx: 0.,
+    });
+
+    assert_eq!(qt.len(), 5);
+    assert!(qt.depth() >= 2);
+    assert_eq!(qt.query_range(&AABBf::from((0., 0., 1., 1.))).len(), 2);
+    assert_eq!(qt.query_point(4., 4.).len(), 1);
+    assert!(qt.overlaps_any(&AABBf::from((6.9, 6.9, 0.1, 0.1))));
+    assert!(!qt.overlaps_any(&AABBf::from((7.1, 7.1, 0.1, 0.1))));
+
+    // Sub-pixel moves re-home the value
+    let watch = qt.watch_zone(AABBf::from((4.5, 0., 0.5, 1.)));
+    let (moved, events) = qt.update_watched(&FRAME);
+    assert!(moved);
+    assert_eq!(
+        events,
+        vec![ZoneEvent::Entered(
+            watch,
+            AABBf::from((4.75, 0.25, 0.5, 0.5))
+        )]
+    );
+    assert!(qt.query_range(&AABBf::from((1.5, 0., 1., 1.))).is_empty());
+    assert_eq!(qt.query_point(5., 0.5), vec![&particle(4.75, 0.25, 3.5)]);
+
+    assert!(qt.remove(&particle(4.75, 0.25, 3.5)).is_some());
+    assert_eq!(qt.len(), 4);
+}
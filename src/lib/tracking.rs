@@ -0,0 +1,237 @@
+use std::ops::{Deref, DerefMut};
+
+use super::geometry::AABB;
+use super::traits::*;
+use super::QuadTree;
+
+/// Stable identifier of a value of a `QuadTreeWithId`,
+/// see `QuadTreeWithId::insert`
+///
+/// An identifier keeps designating its value while the
+/// value moves and the tree is restructured. Once its value
+/// is removed, an identifier designates no other value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ValueId {
+    index: u32,
+    generation: u32,
+}
+
+/// Slot of the slab of a `QuadTreeWithId`
+#[derive(Debug)]
+struct Slot {
+    /// Incremented each time the slot is freed, so that
+    /// the identifiers of removed values no longer match
+    generation: u32,
+    /// Bounding box and sequence number of the value,
+    /// `None` if the slot is free
+    entry: Option<(AABB, u64)>,
+}
+
+/// A `QuadTree` remembering the bounding box of each
+/// of its values by identifier.
+///
+/// The cached box is used as a search hint to find a value
+/// again without walking the whole tree: accessing a value
+/// by its identifier only visits the nodes on the path
+/// leading to it.
+///
+/// Identifiers index a slab of slots, each holding the box
+/// and the insertion sequence number of a value, see
+/// `QuadTree::iter_insertion_order`. Slots of removed values
+/// are reused, but a generation count tells apart the
+/// identifiers of their former and current values.
+///
+/// Every read-only method of `QuadTree` is available
+/// through `Deref`.
+///
+/// Usage:
+/// ```ignore
+/// let mut qt = QuadTree::<Obj>::default().with_id_tracking();
+/// let id = qt.insert(obj);
+/// qt.update_element(id, AABB::from((10, 10, 5, 5)));
+/// qt.get_mut(id).unwrap().speed = 2;
+/// qt.remove_by_id(id);
+/// ```
+#[derive(Debug)]
+pub struct QuadTreeWithId<T: Collidable, M = ()> {
+    tree: QuadTree<T, M>,
+    slots: Vec<Slot>,
+    /// Indices of the free slots
+    free: Vec<u32>,
+}
+
+impl<T: Collidable, M: Default> QuadTreeWithId<T, M> {
+    /// Wraps an existing tree
+    pub fn new(mut tree: QuadTree<T, M>) -> QuadTreeWithId<T, M> {
+        // Values inserted while insertion order was
+        // not tracked all have the sequence number 0
+        tree.next_seq = tree.next_seq.max(1);
+        QuadTreeWithId {
+            tree,
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Inserts a value and returns its identifier
+    pub fn insert(&mut self, v: T) -> ValueId {
+        // Sequence numbers are given even if
+        // insertion order is not tracked
+        let seq = self.tree.next_seq;
+        self.tree.next_seq += 1;
+
+        let bbox = *v.bounding_box();
+        self.tree.watches.inserted(&bbox);
+        self.tree.insert_root_entry(v, seq);
+
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: None,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.entry = Some((bbox, seq));
+        ValueId {
+            index,
+            generation: slot.generation,
+        }
+    }
+
+    /// Same as `insert`
+    pub fn insert_with_id(&mut self, v: T) -> ValueId {
+        self.insert(v)
+    }
+
+    /// Slot of the value with identifier `id`,
+    /// `None` if the value was removed
+    fn slot_mut(&mut self, id: ValueId) -> Option<&mut Slot> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|s| s.generation == id.generation && s.entry.is_some())
+    }
+
+    /// Returns the value with identifier `id`
+    pub fn get(&self, id: ValueId) -> Option<&T> {
+        let slot = self.slots.get(id.index as usize)?;
+        match slot.entry {
+            Some((bbox, seq)) if slot.generation == id.generation => {
+                self.tree.get_entry(&bbox, seq)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value with identifier `id`, mutably
+    ///
+    /// The bounding box of the value may be changed through
+    /// the returned guard: the value is moved to its new place
+    /// in the tree when the guard is dropped.
+    pub fn get_mut(&mut self, id: ValueId) -> Option<ValueMut<'_, T, M>> {
+        let slot = self
+            .slots
+            .get_mut(id.index as usize)
+            .filter(|s| s.generation == id.generation)?;
+        let (bbox, seq) = slot.entry?;
+        self.tree.get_entry(&bbox, seq)?;
+        Some(ValueMut {
+            tree: &mut self.tree,
+            slot,
+            bbox,
+            seq,
+        })
+    }
+
+    /// Removes and returns the value with identifier `id`
+    pub fn remove_by_id(&mut self, id: ValueId) -> Option<T> {
+        let slot = self.slot_mut(id)?;
+        let (bbox, seq) = slot.entry.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+
+        let (v, _) = self.tree.take(&bbox, seq)?;
+        self.tree.watches.removed(v.bounding_box());
+        Some(v)
+    }
+
+    /// Unwraps the tree, forgetting every identifier
+    pub fn into_inner(self) -> QuadTree<T, M> {
+        self.tree
+    }
+}
+
+impl<T: CollidableMut, M: Default> QuadTreeWithId<T, M> {
+    /// Moves the value with identifier `id` to `new_bbox`
+    ///
+    /// The value is taken out of its node, its box is updated
+    /// and it is inserted back in the tree.
+    /// Returns `false` if no value has this identifier.
+    pub fn update_element(&mut self, id: ValueId, new_bbox: AABB) -> bool {
+        match self.get_mut(id) {
+            Some(mut v) => {
+                *v.bounding_box_mut() = new_bbox;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Collidable, M> Deref for QuadTreeWithId<T, M> {
+    type Target = QuadTree<T, M>;
+
+    fn deref(&self) -> &QuadTree<T, M> {
+        &self.tree
+    }
+}
+
+/// Value of a `QuadTreeWithId` borrowed mutably,
+/// see `QuadTreeWithId::get_mut`
+///
+/// Dropping the guard moves the value to its new
+/// place in the tree if its bounding box changed.
+#[derive(Debug)]
+pub struct ValueMut<'a, T: Collidable, M: Default> {
+    tree: &'a mut QuadTree<T, M>,
+    slot: &'a mut Slot,
+    /// Bounding box of the value when it was placed
+    bbox: AABB,
+    seq: u64,
+}
+
+impl<'a, T: Collidable, M: Default> Deref for ValueMut<'a, T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.tree
+            .get_entry(&self.bbox, self.seq)
+            .expect("borrowed value is in the tree")
+    }
+}
+
+impl<'a, T: Collidable, M: Default> DerefMut for ValueMut<'a, T, M> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.tree
+            .get_entry_mut(&self.bbox, self.seq)
+            .expect("borrowed value is in the tree")
+    }
+}
+
+impl<'a, T: Collidable, M: Default> Drop for ValueMut<'a, T, M> {
+    fn drop(&mut self) {
+        let new_bbox = *self.bounding_box();
+        if new_bbox == self.bbox {
+            return;
+        }
+
+        if let Some((v, seq)) = self.tree.take(&self.bbox, self.seq) {
+            self.tree.watches.relocated(&self.bbox, &new_bbox);
+            self.tree.insert_root_entry(v, seq);
+            self.slot.entry = Some((new_bbox, seq));
+        }
+    }
+}
@@ -1,12 +1,52 @@
+use super::geometry::{Coord, Scalar};
 use super::AABB;
 use std::time::Duration;
 
-pub trait Collidable {
-    fn bounding_box(&self) -> &AABB;
+/// Objects with a bounding box of `S` coordinates,
+/// `Coord` by default
+pub trait Collidable<S: Scalar = Coord> {
+    fn bounding_box(&self) -> &AABB<S>;
 }
 
-pub trait Drawable<T> {
-    fn draw(&self, target: &mut T) -> Result<(), String>;
+/// A box is its own bounding box
+impl<S: Scalar> Collidable<S> for AABB<S> {
+    fn bounding_box(&self) -> &AABB<S> {
+        self
+    }
+}
+
+/// A reference has the bounding box of its target,
+/// see `QuadTree::from_refs`
+impl<S: Scalar, U: Collidable<S> + ?Sized> Collidable<S> for &U {
+    fn bounding_box(&self) -> &AABB<S> {
+        (**self).bounding_box()
+    }
+}
+
+/// Collidable objects whose bounding box can be
+/// modified from the outside.
+pub trait CollidableMut<S: Scalar = Coord>: Collidable<S> {
+    fn bounding_box_mut(&mut self) -> &mut AABB<S>;
+}
+
+impl<S: Scalar> CollidableMut<S> for AABB<S> {
+    fn bounding_box_mut(&mut self) -> &mut AABB<S> {
+        self
+    }
+}
+
+/// Objects which can be drawn on a canvas of type `C`
+pub trait Drawable<C> {
+    fn draw(&self, canvas: &mut C) -> Result<(), String>;
+}
+
+/// Canvases on which zones can be outlined,
+/// see `QuadTree::draw_grid`
+pub trait OutlineCanvas {
+    type Color: Copy;
+
+    /// Draws the edges of `zone` in `color`
+    fn outline(&mut self, zone: &AABB, color: Self::Color) -> Result<(), String>;
 }
 
 pub trait Dynamic {
@@ -0,0 +1,110 @@
+use super::geometry::{Coord, Scalar, AABB};
+
+/// Handle on a zone watched by a `QuadTree`, see `QuadTree::watch_zone`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WatchHandle {
+    id: u64,
+}
+
+impl WatchHandle {
+    /// Subscription identifier
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// A value entering or leaving a watched zone
+///
+/// The box is the value's bounding box after the update,
+/// or when it was inserted or removed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ZoneEvent<S: Scalar = Coord> {
+    Entered(WatchHandle, AABB<S>),
+    Left(WatchHandle, AABB<S>),
+}
+
+/// Zones watched by a tree
+#[derive(Debug, Clone)]
+pub(super) struct Watches<S: Scalar = Coord> {
+    zones: Vec<(WatchHandle, AABB<S>)>,
+    next_id: u64,
+    /// Events of inserted and removed values,
+    /// reported by the next update
+    pending: Vec<ZoneEvent<S>>,
+}
+
+impl<S: Scalar> Default for Watches<S> {
+    fn default() -> Self {
+        Watches {
+            zones: Vec::new(),
+            next_id: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<S: Scalar> Watches<S> {
+    pub(super) fn add(&mut self, zone: AABB<S>) -> WatchHandle {
+        let handle = WatchHandle { id: self.next_id };
+        self.next_id += 1;
+        self.zones.push((handle, zone));
+        handle
+    }
+
+    pub(super) fn remove(&mut self, handle: WatchHandle) {
+        self.zones.retain(|(h, _)| *h != handle);
+        self.pending.retain(|e| match e {
+            ZoneEvent::Entered(h, _) | ZoneEvent::Left(h, _) => *h != handle,
+        });
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Pushes the events caused by a value moving from `old` to `new`
+    ///
+    /// A value is in a zone when its box intersects it.
+    pub(super) fn moved(&self, old: &AABB<S>, new: &AABB<S>, events: &mut Vec<ZoneEvent<S>>) {
+        for (handle, zone) in self.zones.iter() {
+            match (old.intersects(zone), new.intersects(zone)) {
+                (false, true) => events.push(ZoneEvent::Entered(*handle, *new)),
+                (true, false) => events.push(ZoneEvent::Left(*handle, *new)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Queues an `Entered` event for each zone a value
+    /// inserted with box `bbox` is in
+    pub(super) fn inserted(&mut self, bbox: &AABB<S>) {
+        for (handle, zone) in self.zones.iter() {
+            if bbox.intersects(zone) {
+                self.pending.push(ZoneEvent::Entered(*handle, *bbox));
+            }
+        }
+    }
+
+    /// Queues a `Left` event for each zone a value
+    /// removed with box `bbox` was in
+    pub(super) fn removed(&mut self, bbox: &AABB<S>) {
+        for (handle, zone) in self.zones.iter() {
+            if bbox.intersects(zone) {
+                self.pending.push(ZoneEvent::Left(*handle, *bbox));
+            }
+        }
+    }
+
+    /// Queues the events of a value moved from `old` to `new`
+    /// outside of an update
+    pub(super) fn relocated(&mut self, old: &AABB<S>, new: &AABB<S>) {
+        let mut pending = std::mem::take(&mut self.pending);
+        self.moved(old, new, &mut pending);
+        self.pending = pending;
+    }
+
+    /// Takes the queued events
+    pub(super) fn take_pending(&mut self) -> Vec<ZoneEvent<S>> {
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -1,6 +1,3 @@
-extern crate sdl2;
-
-mod lib;
 mod vals;
 
 use sdl2::event::Event;
@@ -8,15 +5,14 @@ use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use std::time::Duration;
 
-use rand::Rng;
-
-use lib::geometry::AABB;
-use lib::traits::{Drawable, Dynamic};
+use rust_quad_tree::geometry::AABB;
+use rust_quad_tree::traits::{Drawable, Dynamic};
+use rust_quad_tree::QuadTree;
 use vals::TestVal;
 
 fn main() -> Result<(), String> {
     let dim = 256;
-    let mut qt = lib::QuadTree::<TestVal>::default();
+    let mut qt = QuadTree::<TestVal>::default();
     qt.insert(TestVal {
         bbox: AABB {
             x: 1,
@@ -71,8 +67,6 @@ fn main() -> Result<(), String> {
     canvas.present();
     let mut event_pump = sdl_context.event_pump()?;
 
-    let mut rng = rand::thread_rng();
-
     'running: loop {
         for event in event_pump.poll_iter() {
             match event {
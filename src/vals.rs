@@ -1,13 +1,10 @@
-extern crate sdl2;
-
-use crate::lib::geometry::AABB;
-use crate::lib::traits::*;
+use rust_quad_tree::geometry::AABB;
+use rust_quad_tree::traits::*;
 
 use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget};
 use std::time::Duration;
 
-type WinCanvas = sdl2::render::Canvas<sdl2::video::Window>;
-
 #[derive(Debug)]
 pub struct TestVal {
     pub bbox: AABB,
@@ -15,13 +12,19 @@ pub struct TestVal {
 
 impl Collidable for TestVal {
     fn bounding_box(&self) -> &AABB {
-        return &self.bbox;
+        &self.bbox
+    }
+}
+
+impl CollidableMut for TestVal {
+    fn bounding_box_mut(&mut self) -> &mut AABB {
+        &mut self.bbox
     }
 }
 
-impl Drawable<WinCanvas> for TestVal {
-    fn draw(&self, canvas: &mut WinCanvas) -> Result<(), String> {
-        let rect = Rect::new(self.bbox.x, self.bbox.y, self.bbox.w, self.bbox.h);
+impl<RT: RenderTarget> Drawable<Canvas<RT>> for TestVal {
+    fn draw(&self, canvas: &mut Canvas<RT>) -> Result<(), String> {
+        let rect = Rect::from(&self.bbox);
         canvas.draw_rect(rect)
     }
 }
@@ -29,6 +32,6 @@ impl Drawable<WinCanvas> for TestVal {
 impl Dynamic for TestVal {
     fn update(&mut self, _delta: &Duration) -> bool {
         self.bbox.translate(1, 0);
-        return true;
+        true
     }
 }
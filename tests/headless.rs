@@ -0,0 +1,73 @@
+//! Uses the crate through its public API only, none of which
+//! needs SDL2: run with `--no-default-features`.
+
+use std::time::Duration;
+
+use rust_quad_tree::builder::QuadTreeBuilder;
+use rust_quad_tree::geometry::{Coord, Quadrant, AABB};
+use rust_quad_tree::traits::{Collidable, Dynamic};
+use rust_quad_tree::QuadTree;
+
+#[derive(Debug, PartialEq)]
+struct Body {
+    bbox: AABB,
+    speed: Coord,
+}
+
+impl Body {
+    fn new(x: Coord, y: Coord, speed: Coord) -> Body {
+        Body {
+            bbox: AABB::from((x, y, 4, 4)),
+            speed,
+        }
+    }
+}
+
+impl Collidable for Body {
+    fn bounding_box(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+impl Dynamic for Body {
+    fn update(&mut self, _delta: &Duration) -> bool {
+        self.bbox.translate(self.speed, 0);
+        self.speed != 0
+    }
+}
+
+#[test]
+fn index_query_and_update_without_sdl() {
+    let mut qt: QuadTree<Body> = QuadTreeBuilder::new()
+        .zone(AABB::from((0, 0, 512, 512)))
+        .max_values(2)
+        .max_depth(5)
+        .build();
+    qt.insert(Body::new(10, 10, 300));
+    qt.insert(Body::new(20, 20, 0));
+    qt.insert(Body::new(400, 400, 0));
+    qt.insert(Body::new(250, 250, 0));
+
+    assert_eq!(qt.len(), 4);
+    assert_eq!(qt.query_range(&AABB::from((0, 0, 64, 64))).len(), 2);
+    assert_eq!(qt.nearest(402, 398).map(|b| b.bbox.x), Some(400));
+
+    assert!(qt.update(&Duration::from_millis(16)));
+    assert_eq!(qt.query_point(312, 12), vec![&Body::new(310, 10, 300)]);
+    assert_eq!(qt.query_range(&AABB::from((0, 0, 64, 64))).len(), 1);
+    assert!(qt.zone_of_path(&[Quadrant::TopLeft]).is_some());
+}
+
+#[test]
+fn geometry_without_sdl() {
+    let a: AABB = AABB::from((0, 0, 10, 10));
+    let b: AABB = AABB::from((5, 5, 10, 10));
+
+    assert!(a.intersects(&b));
+    assert_eq!(a.intersection(&b), Some(AABB::from((5, 5, 5, 5))));
+    assert_eq!(a.union(&b), AABB::from((0, 0, 15, 15)));
+    assert_eq!(
+        Quadrant::quadrant_bbox(&a, Quadrant::BottomRight),
+        AABB::from((5, 5, 5, 5))
+    );
+}